@@ -1,7 +1,11 @@
-use soroban_sdk::{Address, Env};
+use soroban_sdk::{panic_with_error, Address, Env, TryFromVal, Val, Vec};
 
+use crate::error::ExcellarError;
+use crate::event::kyc_expired_event;
+use crate::registry::{read_identity_registry, registry_is_blacklisted, registry_kyc_record};
 use crate::storage_types::{
-    DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT,
+    BlacklistRewardPolicy, DataKey, KycRecord, KycRevocationRewardPolicy, KycTier, PendingAdmin,
+    BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD, DEFAULT_KYC_VALIDITY, INSTANCE_BUMP_AMOUNT,
     INSTANCE_LIFETIME_THRESHOLD,
 };
 
@@ -18,7 +22,10 @@ pub fn read_administrator(e: &Env) -> Address {
     e.storage()
         .instance()
         .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
-    e.storage().instance().get(&key).unwrap()
+    match e.storage().instance().get(&key) {
+        Some(admin) => admin,
+        None => panic_with_error!(e, ExcellarError::NotInitialized),
+    }
 }
 
 pub fn write_administrator(e: &Env, id: &Address) {
@@ -29,38 +36,159 @@ pub fn write_administrator(e: &Env, id: &Address) {
     e.storage().instance().set(&key, id);
 }
 
-pub fn write_kyc(e: &Env, addr: Address) {
+pub fn read_pending_admin(e: &Env) -> Option<PendingAdmin> {
+    let key = DataKey::PendingAdmin;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().get(&key)
+}
+
+pub fn write_pending_admin(e: &Env, new_admin: &Address, expiration_ledger: u32) {
+    if expiration_ledger < e.ledger().sequence() {
+        panic_with_error!(e, ExcellarError::InvalidExpirationLedger)
+    }
+    let key = DataKey::PendingAdmin;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().set(
+        &key,
+        &PendingAdmin {
+            new_admin: new_admin.clone(),
+            expiration_ledger,
+        },
+    );
+}
+
+pub fn remove_pending_admin(e: &Env) {
+    let key = DataKey::PendingAdmin;
+    e.storage().instance().remove(&key);
+}
+
+fn new_kyc_record(e: &Env, tier: KycTier) -> KycRecord {
+    let verified_at = e.ledger().sequence();
+    KycRecord {
+        tier,
+        verified_at,
+        expires_at: verified_at.saturating_add(read_kyc_validity(e)),
+    }
+}
+
+pub fn write_kyc(e: &Env, addr: Address, tier: KycTier) {
+    let key = DataKey::Kyc(addr.clone());
+    e.storage().persistent().set(&key, &new_kyc_record(e, tier));
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    e.storage()
+        .persistent()
+        .remove(&DataKey::KycExpiryReported(addr));
+}
+
+pub fn read_kyc(e: &Env, addr: Address) -> Option<KycRecord> {
+    let Some(config) = read_identity_registry(e) else {
+        return read_local_kyc(e, addr);
+    };
+    match registry_kyc_record(e, &config, &addr) {
+        None if config.local_fallback => read_local_kyc(e, addr),
+        record => record,
+    }
+}
+
+fn read_local_kyc(e: &Env, addr: Address) -> Option<KycRecord> {
     let key = DataKey::Kyc(addr);
-    e.storage().persistent().set(&key, &true);
+    let val = e.storage().persistent().get::<DataKey, Val>(&key)?;
     e.storage()
         .persistent()
         .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    if let Ok(record) = KycRecord::try_from_val(e, &val) {
+        return Some(record);
+    }
+    // entries written before expiry tracking hold a bare `true`, they become
+    // a record whose validity runs from the ledger they are migrated on. No
+    // tier is assumed, the compliance operator assigns one through `pass_kyc`
+    match bool::try_from_val(e, &val) {
+        Ok(true) => {
+            let record = new_kyc_record(e, KycTier::Unassigned);
+            e.storage().persistent().set(&key, &record);
+            Some(record)
+        }
+        _ => None,
+    }
+}
+
+// with a registry and no local fallback the local records are never read, so
+// writing them would change nothing
+pub fn check_local_kyc_used(e: &Env) {
+    if let Some(config) = read_identity_registry(e) {
+        if !config.local_fallback {
+            panic_with_error!(e, ExcellarError::KycManagedByRegistry)
+        }
+    }
 }
 
 pub fn remove_kyc(e: &Env, addr: Address) {
-    let key = DataKey::Kyc(addr);
+    let key = DataKey::Kyc(addr.clone());
     e.storage().persistent().remove(&key);
+    e.storage()
+        .persistent()
+        .remove(&DataKey::KycExpiryReported(addr));
 }
 
 pub fn is_kyc_passed(e: &Env, addr: Address) -> bool {
-    let key = DataKey::Kyc(addr);
-    if let Some(val) = e.storage().persistent().get(&key) {
-        e.storage()
-            .persistent()
-            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
-        return val;
+    match read_kyc(e, addr) {
+        Some(record) => record.expires_at >= e.ledger().sequence(),
+        None => false,
     }
+}
 
-    false
+/// Publishes `kyc_expired` the first time a state change finds the KYC of
+/// `addr` lapsed, views and later calls stay silent until it is renewed.
+pub fn report_kyc_expiry(e: &Env, addr: Address) {
+    let Some(record) = read_kyc(e, addr.clone()) else {
+        return;
+    };
+    let key = DataKey::KycExpiryReported(addr.clone());
+    if record.expires_at >= e.ledger().sequence() || e.storage().persistent().has(&key) {
+        return;
+    }
+    e.storage().persistent().set(&key, &true);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    kyc_expired_event(e, addr, record.expires_at);
 }
 
 pub fn check_kyc_passed(e: &Env, addr: Address) {
-    let passed = is_kyc_passed(e, addr);
-    if !passed {
-        panic!("address is not passed kyc");
+    match read_kyc(e, addr) {
+        None => panic_with_error!(e, ExcellarError::KycNotPassed),
+        Some(record) if record.expires_at < e.ledger().sequence() => {
+            panic_with_error!(e, ExcellarError::KycExpired)
+        }
+        Some(_) => {}
     }
 }
 
+pub fn read_kyc_validity(e: &Env) -> u32 {
+    let key = DataKey::KycValidity;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage()
+        .instance()
+        .get(&key)
+        .unwrap_or(DEFAULT_KYC_VALIDITY)
+}
+
+pub fn write_kyc_validity(e: &Env, ledgers: u32) {
+    let key = DataKey::KycValidity;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().set(&key, &ledgers);
+}
+
 pub fn remove_blacklist(e: &Env, addr: Address) {
     let key = DataKey::Blacklisted(addr);
     e.storage().persistent().remove(&key);
@@ -74,26 +202,131 @@ pub fn write_blacklist(e: &Env, addr: Address) {
         .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
 }
 
-pub fn check_not_blacklisted(e: &Env, addr: Address) {
-    let key = DataKey::Blacklisted(addr);
-    if let Some(val) = e.storage().persistent().get::<DataKey, bool>(&key) {
+pub fn is_blacklisted(e: &Env, addr: Address) -> bool {
+    let key = DataKey::Blacklisted(addr.clone());
+    if let Some(true) = e.storage().persistent().get(&key) {
         e.storage()
             .persistent()
             .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
-        assert!(!val, "address is blacklisted");
+        return true;
+    }
+    // the local blacklist always applies, the registry can only add to it
+    match read_identity_registry(e) {
+        Some(config) => registry_is_blacklisted(e, &config, &addr),
+        None => false,
     }
 }
+
+pub fn check_not_blacklisted(e: &Env, addr: Address) {
+    if is_blacklisted(e, addr) {
+        panic_with_error!(e, ExcellarError::Blacklisted);
+    }
+}
+
+pub fn read_blacklist_reward_policy(e: &Env) -> BlacklistRewardPolicy {
+    let key = DataKey::BlacklistRewardPolicy;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage()
+        .instance()
+        .get(&key)
+        .unwrap_or(BlacklistRewardPolicy::Freeze)
+}
+
+pub fn write_blacklist_reward_policy(e: &Env, policy: BlacklistRewardPolicy) {
+    let key = DataKey::BlacklistRewardPolicy;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().set(&key, &policy);
+}
+pub fn read_kyc_revocation_reward_policy(e: &Env) -> KycRevocationRewardPolicy {
+    let key = DataKey::KycRevocationRewardPolicy;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage()
+        .instance()
+        .get(&key)
+        .unwrap_or(KycRevocationRewardPolicy::Freeze)
+}
+
+pub fn write_kyc_revocation_reward_policy(e: &Env, policy: KycRevocationRewardPolicy) {
+    let key = DataKey::KycRevocationRewardPolicy;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().set(&key, &policy);
+}
+
+// policy the KYC of the address was last revoked under, until it passes KYC
+// again; only `Keep` pays for the revoked period once KYC is restored
+pub fn read_kyc_revocation(e: &Env, addr: Address) -> Option<KycRevocationRewardPolicy> {
+    let key = DataKey::KycRevoked(addr);
+    e.storage().persistent().get(&key)
+}
+
+pub fn write_kyc_revocation(e: &Env, addr: Address, policy: Option<KycRevocationRewardPolicy>) {
+    let key = DataKey::KycRevoked(addr);
+    match policy {
+        Some(policy) => {
+            e.storage().persistent().set(&key, &policy);
+            e.storage().persistent().extend_ttl(
+                &key,
+                BALANCE_LIFETIME_THRESHOLD,
+                BALANCE_BUMP_AMOUNT,
+            );
+        }
+        None => e.storage().persistent().remove(&key),
+    }
+}
+
 pub fn remove_amm(e: &Env, addr: Address) {
-    let key = DataKey::Amm(addr);
+    let key = DataKey::Amm(addr.clone());
     e.storage().persistent().remove(&key);
+
+    let mut amms = read_amm_list(e);
+    if let Some(position) = amms.first_index_of(&addr) {
+        amms.remove(position);
+        write_amm_list(e, &amms);
+    }
 }
 
 pub fn add_amm(e: &Env, addr: Address) {
-    let key = DataKey::Amm(addr);
+    let key = DataKey::Amm(addr.clone());
     e.storage().persistent().set(&key, &true);
     e.storage()
         .persistent()
         .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+
+    let mut amms = read_amm_list(e);
+    if !amms.contains(&addr) {
+        amms.push_back(addr);
+        write_amm_list(e, &amms);
+    }
+}
+
+pub fn read_amm_list(e: &Env) -> Vec<Address> {
+    let key = DataKey::AmmList;
+    if let Some(amms) = e.storage().persistent().get::<DataKey, Vec<Address>>(&key) {
+        e.storage().persistent().extend_ttl(
+            &key,
+            INSTANCE_LIFETIME_THRESHOLD,
+            INSTANCE_BUMP_AMOUNT,
+        );
+        amms
+    } else {
+        Vec::new(e)
+    }
+}
+
+fn write_amm_list(e: &Env, amms: &Vec<Address>) {
+    let key = DataKey::AmmList;
+    e.storage().persistent().set(&key, amms);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 }
 
 pub fn is_amm(e: &Env, addr: Address) -> bool {
@@ -109,7 +342,7 @@ pub fn is_amm(e: &Env, addr: Address) -> bool {
 
 pub fn check_not_amm(e: &Env, addr: Address) {
     if is_amm(e, addr.clone()) {
-        panic!("amm address not allowed")
+        panic_with_error!(e, ExcellarError::AmmNotAllowed)
     }
 }
 pub fn require_admin(e: &Env) -> Address {
@@ -1,5 +1,6 @@
-use soroban_sdk::{Address, Env};
+use soroban_sdk::{panic_with_error, Address, Env};
 
+use crate::error::ExcellarError;
 use crate::storage_types::{AllowanceDataKey, AllowanceValue, DataKey};
 
 pub fn read_allowance(e: &Env, from: Address, spender: Address) -> AllowanceValue {
@@ -34,7 +35,7 @@ pub fn write_allowance(
     };
 
     if amount > 0 && expiration_ledger < e.ledger().sequence() {
-        panic!("expiration_ledger is less than ledger seq when amount > 0")
+        panic_with_error!(e, ExcellarError::InvalidExpirationLedger)
     }
 
     let key = DataKey::Allowance(AllowanceDataKey { from, spender });
@@ -52,7 +53,7 @@ pub fn write_allowance(
 pub fn spend_allowance(e: &Env, from: Address, spender: Address, amount: i128) {
     let allowance = read_allowance(e, from.clone(), spender.clone());
     if allowance.amount < amount {
-        panic!("insufficient allowance");
+        panic_with_error!(e, ExcellarError::InsufficientAllowance);
     }
     if amount > 0 {
         write_allowance(
@@ -1,5 +1,7 @@
+use crate::error::ExcellarError;
+use crate::fixed_point::{mul_div, mul_div_rem, Rounding};
 use crate::reward::reset_reward;
-use soroban_sdk::{contracttype, Address, Env, Vec};
+use soroban_sdk::{contracttype, panic_with_error, Address, Env, Vec};
 
 use crate::storage_types::{DataKey, INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD};
 
@@ -45,11 +47,12 @@ pub fn update_amm_depositor_balance(
     depositor_address: Address,
     amount: i128,
 ) {
+    if amount <= 0 {
+        reduce_amm_depositors_pro_rata(e, amm_address, -amount);
+        return;
+    }
     let mut depositors = match get_amm_depositors(e, amm_address.clone()) {
         None => {
-            if amount < 0 {
-                panic!("tried to withdraw from a pool without any deposits")
-            };
             let mut depositors: Vec<AmmDepositor> = Vec::new(e);
             depositors.push_back(AmmDepositor {
                 depositor: depositor_address.clone(),
@@ -62,20 +65,28 @@ pub fn update_amm_depositor_balance(
         Some(depositor) => depositor,
     };
 
-    if amount > 0 {
-        let depositor = depositors.iter().find(|d| d.depositor == depositor_address);
-        match depositor {
-            Some(mut depositor) => depositor.balance += amount,
-            None => depositors.push_back(AmmDepositor {
-                depositor: depositor_address,
-                balance: amount,
-            }),
-        }
-        set_amm_depositors(e, amm_address, depositors);
+    let depositor = depositors.iter().find(|d| d.depositor == depositor_address);
+    match depositor {
+        Some(mut depositor) => depositor.balance += amount,
+        None => depositors.push_back(AmmDepositor {
+            depositor: depositor_address,
+            balance: amount,
+        }),
+    }
+    set_amm_depositors(e, amm_address, depositors);
+}
+
+/// Takes `total_outflow` out of the pool, from every depositor in proportion
+/// to their deposit.
+pub fn reduce_amm_depositors_pro_rata(e: &Env, amm_address: Address, total_outflow: i128) {
+    if total_outflow == 0 {
         return;
     }
+    let mut depositors = match get_amm_depositors(e, amm_address.clone()) {
+        None => panic_with_error!(e, ExcellarError::AmmPoolEmpty),
+        Some(depositor) => depositor,
+    };
 
-    let total_outflow = -amount;
     let total_balance: i128 = depositors.iter().map(|d| d.balance).sum();
     if total_outflow >= total_balance {
         // Clear the list of depositors and return
@@ -87,15 +98,17 @@ pub fn update_amm_depositor_balance(
     // Calculate the new balances and the rounding errors for each depositor
     let mut new_balances: Vec<i128> = Vec::new(e);
     let mut rounding_errors: Vec<i128> = Vec::new(e);
+    let mut taken_outflow = 0;
     for depositor in depositors.iter() {
-        let new_balance = depositor.balance - depositor.balance * total_outflow / total_balance;
-        let rounding_error = depositor.balance * total_outflow % total_balance;
-        new_balances.push_back(new_balance);
+        let (taken, rounding_error) =
+            mul_div_rem(e, depositor.balance, total_outflow, total_balance);
+        new_balances.push_back(depositor.balance - taken);
         rounding_errors.push_back(rounding_error);
+        taken_outflow += taken;
     }
 
     // Distribute the rounding errors to the depositors with the largest balances
-    let mut remaining_outflow = total_outflow - new_balances.iter().sum::<i128>();
+    let mut remaining_outflow = total_outflow - taken_outflow;
     while remaining_outflow > 0 {
         let max_error_index = rounding_errors
             .iter()
@@ -126,6 +139,7 @@ pub fn update_amm_depositor_balance(
 }
 
 pub fn calculate_amm_reward_share(
+    e: &Env,
     total_reward: i128,
     depositor_balance: i128,
     total_balance: i128,
@@ -134,9 +148,14 @@ pub fn calculate_amm_reward_share(
         return 0;
     }
     let scale_factor = 1_000_000_i128;
-    let scaled_depositor_balance = depositor_balance * scale_factor;
-    let participation = scaled_depositor_balance / total_balance;
-    (total_reward * participation) / scale_factor
+    let participation = mul_div(
+        e,
+        depositor_balance,
+        scale_factor,
+        total_balance,
+        Rounding::Down,
+    );
+    mul_div(e, total_reward, participation, scale_factor, Rounding::Down)
 }
 
 #[cfg(test)]
@@ -148,7 +167,11 @@ mod test {
         Address, Env, IntoVal, Symbol,
     };
 
-    use crate::amm::calculate_amm_reward_share;
+    use crate::amm::{
+        calculate_amm_reward_share, get_amm_depositors, reduce_amm_depositors_pro_rata,
+        update_amm_depositor_balance,
+    };
+    use crate::storage_types::KycTier;
     use crate::test::set_sequence_number;
 
     #[test]
@@ -160,7 +183,7 @@ mod test {
         let amm_address = Address::generate(&e);
         let token = crate::test::create_token(&e, &admin);
 
-        token.add_amm_address(&amm_address);
+        token.add_amm_address(&admin, &amm_address);
         assert_eq!(
             e.auths(),
             std::vec![(
@@ -169,7 +192,7 @@ mod test {
                     function: AuthorizedFunction::Contract((
                         token.address.clone(),
                         Symbol::new(&e, "add_amm_address"),
-                        (&amm_address,).into_val(&e),
+                        (&admin, &amm_address).into_val(&e),
                     )),
                     sub_invocations: std::vec![],
                 }
@@ -186,7 +209,7 @@ mod test {
         let amm_address = Address::generate(&e);
         let token = crate::test::create_token(&e, &admin);
 
-        token.remove_amm_address(&amm_address);
+        token.remove_amm_address(&admin, &amm_address);
         assert_eq!(
             e.auths(),
             std::vec![(
@@ -195,7 +218,7 @@ mod test {
                     function: AuthorizedFunction::Contract((
                         token.address.clone(),
                         Symbol::new(&e, "remove_amm_address"),
-                        (&amm_address,).into_val(&e),
+                        (&admin, &amm_address).into_val(&e),
                     )),
                     sub_invocations: std::vec![],
                 }
@@ -204,19 +227,19 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "address is not passed kyc")]
+    #[should_panic(expected = "Error(Contract, #5)")]
     fn test_mint_not_allowed_for_amm() {
         let e = Env::default();
         e.mock_all_auths();
         let admin = Address::generate(&e);
         let amm_address = Address::generate(&e);
         let token = crate::test::create_token(&e, &admin);
-        token.add_amm_address(&amm_address);
-        token.mint(&amm_address, &1000);
+        token.add_amm_address(&admin, &amm_address);
+        token.mint(&admin, &amm_address, &1000);
     }
 
     #[test]
-    #[should_panic(expected = "address is not passed kyc")]
+    #[should_panic(expected = "Error(Contract, #5)")]
     fn test_burn_not_allowed_for_amm() {
         let e = Env::default();
         e.mock_all_auths();
@@ -224,13 +247,92 @@ mod test {
         let amm_address = Address::generate(&e);
         let user = Address::generate(&e);
         let token = crate::test::create_token(&e, &admin);
-        token.pass_kyc(&user);
-        token.add_amm_address(&amm_address);
-        token.mint(&user, &1000);
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+        token.add_amm_address(&admin, &amm_address);
+        token.mint(&admin, &user, &1000);
         token.transfer(&user, &amm_address, &100);
         token.burn(&amm_address, &100);
     }
 
+    #[test]
+    fn test_burn_from_amm() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let admin = Address::generate(&e);
+        let amm_address = Address::generate(&e);
+        let redeemer = Address::generate(&e);
+        let user1 = Address::generate(&e);
+        let user2 = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        let blocks_per_reward: u32 = 28_800;
+        let reward_rate: u32 = 30_000_000;
+        token.set_reward_tick(&admin, &blocks_per_reward);
+        token.set_reward_rate(&admin, &reward_rate);
+        token.pass_kyc(&admin, &user1, &KycTier::Retail);
+        token.pass_kyc(&admin, &user2, &KycTier::Retail);
+        token.add_amm_address(&admin, &amm_address);
+        set_sequence_number(&e, 0);
+        token.mint(&admin, &user1, &500);
+        token.mint(&admin, &user2, &500);
+        token.transfer(&user1, &amm_address, &500);
+        token.transfer(&user2, &amm_address, &500);
+
+        // burning half the pool halves each depositor's share
+        token.approve(&amm_address, &redeemer, &500, &200);
+        token.burn_from(&redeemer, &amm_address, &500);
+        assert_eq!(token.balance(&amm_address), 500);
+
+        set_sequence_number(&e, blocks_per_reward);
+        token.transfer(&amm_address, &user1, &0);
+        token.claim_reward(&user1);
+        token.claim_reward(&user2);
+        assert_eq!(token.balance(&user1), 75);
+        assert_eq!(token.balance(&user2), 75);
+    }
+
+    #[test]
+    fn test_clawback_from_amm() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let admin = Address::generate(&e);
+        let amm_address = Address::generate(&e);
+        let user1 = Address::generate(&e);
+        let user2 = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        let blocks_per_reward: u32 = 28_800;
+        token.set_reward_tick(&admin, &blocks_per_reward);
+        token.set_reward_rate(&admin, &30_000_000);
+        token.pass_kyc(&admin, &user1, &KycTier::Retail);
+        token.pass_kyc(&admin, &user2, &KycTier::Retail);
+        token.add_amm_address(&admin, &amm_address);
+        set_sequence_number(&e, 0);
+        token.mint(&admin, &user1, &200);
+        token.mint(&admin, &user2, &600);
+        token.transfer(&user1, &amm_address, &200);
+        token.transfer(&user2, &amm_address, &600);
+
+        token.clawback(&admin, &amm_address, &400, &1);
+        assert_eq!(token.balance(&amm_address), 400);
+        // half the pool is seized, so each depositor keeps half
+        let depositors = e
+            .as_contract(&token.address, || {
+                get_amm_depositors(&e, amm_address.clone())
+            })
+            .unwrap();
+        assert_eq!(depositors.len(), 2);
+        assert_eq!(depositors.get(0).unwrap().depositor, user1);
+        assert_eq!(depositors.get(0).unwrap().balance, 100);
+        assert_eq!(depositors.get(1).unwrap().depositor, user2);
+        assert_eq!(depositors.get(1).unwrap().balance, 300);
+
+        set_sequence_number(&e, blocks_per_reward);
+        token.transfer(&amm_address, &user1, &0);
+        token.claim_reward(&user1);
+        token.claim_reward(&user2);
+        assert_eq!(token.balance(&user1), 30);
+        assert_eq!(token.balance(&user2), 90);
+    }
+
     #[test]
     fn test_update_amm_depositor_balance() {
         let e = Env::default();
@@ -245,16 +347,16 @@ mod test {
         let blocks_per_reward: u32 = 28_800;
         // 1% per cycle
         let reward_rate: u32 = 1_000_000;
-        token.set_reward_tick(&blocks_per_reward);
-        token.set_reward_rate(&reward_rate);
+        token.set_reward_tick(&admin, &blocks_per_reward);
+        token.set_reward_rate(&admin, &reward_rate);
 
-        token.pass_kyc(&user1);
-        token.pass_kyc(&user2);
-        token.pass_kyc(&user3);
-        token.add_amm_address(&amm_address);
-        token.mint(&user1, &1000);
-        token.mint(&user2, &1000);
-        token.mint(&user3, &1000);
+        token.pass_kyc(&admin, &user1, &KycTier::Retail);
+        token.pass_kyc(&admin, &user2, &KycTier::Retail);
+        token.pass_kyc(&admin, &user3, &KycTier::Retail);
+        token.add_amm_address(&admin, &amm_address);
+        token.mint(&admin, &user1, &1000);
+        token.mint(&admin, &user2, &1000);
+        token.mint(&admin, &user3, &1000);
 
         token.transfer(&user1, &amm_address, &300);
         set_sequence_number(&e, 0);
@@ -274,25 +376,67 @@ mod test {
         token.claim_reward(&user2);
         token.claim_reward(&user3);
 
-        assert_eq!(token.balance(&user1), 1018);
-        assert_eq!(token.balance(&user2), 1019);
-        assert_eq!(token.balance(&user3), 1029);
+        // each depositor earns 1% per cycle on the balance it keeps plus its
+        // share of the pool, which outflows cut pro rata and which rounds
+        // down every time the pool settles:
+        // user1 14 + 30 kept, 2 + 3 + 2 + 1 pooled
+        // user2 18 + 20 kept, 3 + 3 + 2 + 1 pooled
+        // user3 10 + 15 + 10 kept, 4 + 3 + 2 pooled
+        // before the reward index, crediting a pool share also restarted the
+        // depositor's own accrual, which is where 1018, 1019 and 1029 came
+        // from; see test_pool_share_does_not_reset_depositor_accrual
+        assert_eq!(token.balance(&user1), 1052);
+        assert_eq!(token.balance(&user2), 1047);
+        assert_eq!(token.balance(&user3), 1044);
+    }
+
+    #[test]
+    fn test_pro_rata_outflow_over_large_balances() {
+        let e = Env::default();
+        let admin = Address::generate(&e);
+        let amm_address = Address::generate(&e);
+        let user1 = Address::generate(&e);
+        let user2 = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+
+        // the products of these balances and the outflow overflow i128
+        let depositors = e.as_contract(&token.address, || {
+            update_amm_depositor_balance(&e, amm_address.clone(), user1.clone(), i128::MAX / 2);
+            update_amm_depositor_balance(&e, amm_address.clone(), user2.clone(), i128::MAX / 4);
+            reduce_amm_depositors_pro_rata(&e, amm_address.clone(), i128::MAX / 3);
+            get_amm_depositors(&e, amm_address.clone()).unwrap()
+        });
+        // each keeps 5/9, the unit left over by rounding comes off the
+        // larger remainder
+        assert_eq!(
+            depositors.get(0).unwrap().balance,
+            47_261_439_850_130_342_147_690_917_698_856_696_035
+        );
+        assert_eq!(
+            depositors.get(1).unwrap().balance,
+            23_630_719_925_065_171_073_845_458_849_428_348_017
+        );
+        assert_eq!(
+            depositors.iter().map(|d| d.balance).sum::<i128>(),
+            i128::MAX / 2 + i128::MAX / 4 - i128::MAX / 3
+        );
     }
 
     #[test]
     fn test_calculate_amm_reward_share() {
+        let e = Env::default();
         // Test case where reward share is calculated correctly
         let total_reward = 1000;
         let total_balance = 500;
-        let reward_share = calculate_amm_reward_share(total_reward, 100, total_balance);
+        let reward_share = calculate_amm_reward_share(&e, total_reward, 100, total_balance);
         assert_eq!(reward_share, 200);
 
         // Test case where reward share is zero due to zero total balance
-        let reward_share = calculate_amm_reward_share(total_reward, 100, 0);
+        let reward_share = calculate_amm_reward_share(&e, total_reward, 100, 0);
         assert_eq!(reward_share, 0);
 
         // Test case where reward share is zero due to zero depositor balance
-        let reward_share = calculate_amm_reward_share(total_reward, 0, total_balance);
+        let reward_share = calculate_amm_reward_share(&e, total_reward, 0, total_balance);
         assert_eq!(reward_share, 0);
     }
 
@@ -315,15 +459,15 @@ mod test {
         // 30% per cycle
         let reward_rate: u32 = 30_000_000;
 
-        token.pass_kyc(&user1);
-        token.pass_kyc(&user2);
+        token.pass_kyc(&admin, &user1, &KycTier::Retail);
+        token.pass_kyc(&admin, &user2, &KycTier::Retail);
         set_sequence_number(&e, 0);
-        token.set_reward_tick(&blocks_per_reward);
-        token.set_reward_rate(&reward_rate);
-        token.add_amm_address(&amm1);
-        token.add_amm_address(&amm2);
+        token.set_reward_tick(&admin, &blocks_per_reward);
+        token.set_reward_rate(&admin, &reward_rate);
+        token.add_amm_address(&admin, &amm1);
+        token.add_amm_address(&admin, &amm2);
 
-        token.mint(&user1, &1000);
+        token.mint(&admin, &user1, &1000);
         token.transfer(&user1, &amm1, &1000);
         assert_eq!(token.balance(&user1), 0);
         assert_eq!(token.balance(&user2), 0);
@@ -340,10 +484,10 @@ mod test {
         set_sequence_number(&e, 2 * blocks_per_reward);
         token.claim_reward(&user1);
         token.claim_reward(&user2);
-        token.remove_amm_address(&amm1);
-        token.remove_amm_address(&amm2);
-        token.pass_kyc(&amm1);
-        token.pass_kyc(&amm2);
+        token.remove_amm_address(&admin, &amm1);
+        token.remove_amm_address(&admin, &amm2);
+        token.pass_kyc(&admin, &amm1, &KycTier::Retail);
+        token.pass_kyc(&admin, &amm2, &KycTier::Retail);
 
         token.claim_reward(&amm1);
         token.claim_reward(&amm2);
@@ -371,14 +515,14 @@ mod test {
 
         let blocks_per_reward: u32 = 28_800;
         let reward_rate: u32 = 30_000_000;
-        token.set_reward_tick(&blocks_per_reward);
-        token.set_reward_rate(&reward_rate);
-        token.pass_kyc(&user1);
-        token.pass_kyc(&user2);
-        token.pass_kyc(&sink);
-        token.add_amm_address(&amm);
+        token.set_reward_tick(&admin, &blocks_per_reward);
+        token.set_reward_rate(&admin, &reward_rate);
+        token.pass_kyc(&admin, &user1, &KycTier::Retail);
+        token.pass_kyc(&admin, &user2, &KycTier::Retail);
+        token.pass_kyc(&admin, &sink, &KycTier::Retail);
+        token.add_amm_address(&admin, &amm);
         set_sequence_number(&e, 0);
-        token.mint(&user1, &800);
+        token.mint(&admin, &user1, &800);
         token.transfer(&user1, &amm, &800);
         set_sequence_number(&e, blocks_per_reward);
         token.transfer(&amm, &sink, &400);
@@ -389,7 +533,7 @@ mod test {
         token.burn(&user1, &240);
         assert_eq!(token.balance(&user1), 0);
         // Fund with with user2
-        token.mint(&user2, &800);
+        token.mint(&admin, &user2, &800);
         token.transfer(&user2, &amm, &800);
         set_sequence_number(&e, blocks_per_reward * 2);
         token.transfer(&amm, &sink, &1200);
@@ -413,14 +557,14 @@ mod test {
         let blocks_per_reward: u32 = 28_800;
         let reward_rate: u32 = 30_000_000;
 
-        token.set_reward_tick(&blocks_per_reward);
-        token.set_reward_rate(&reward_rate);
-        token.pass_kyc(&user1);
-        token.pass_kyc(&user2);
-        token.add_amm_address(&sink); // only change in setup, sink is amm
-        token.add_amm_address(&amm);
+        token.set_reward_tick(&admin, &blocks_per_reward);
+        token.set_reward_rate(&admin, &reward_rate);
+        token.pass_kyc(&admin, &user1, &KycTier::Retail);
+        token.pass_kyc(&admin, &user2, &KycTier::Retail);
+        token.add_amm_address(&admin, &sink); // only change in setup, sink is amm
+        token.add_amm_address(&admin, &amm);
         set_sequence_number(&e, 0);
-        token.mint(&user1, &800);
+        token.mint(&admin, &user1, &800);
         token.transfer(&user1, &amm, &800);
         set_sequence_number(&e, blocks_per_reward);
         token.transfer(&amm, &sink, &400);
@@ -431,7 +575,7 @@ mod test {
         token.burn(&user1, &240);
         assert_eq!(token.balance(&user1), 0);
         // Fund with with user2
-        token.mint(&user2, &800);
+        token.mint(&admin, &user2, &800);
         token.transfer(&user2, &amm, &800);
         set_sequence_number(&e, blocks_per_reward * 2);
         token.transfer(&amm, &sink, &1200);
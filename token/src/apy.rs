@@ -0,0 +1,223 @@
+use soroban_sdk::{panic_with_error, Env};
+
+use crate::clock::units_per_year;
+use crate::error::ExcellarError;
+use crate::fixed_point::{checked_mul_div, Rounding};
+
+// fixed-point scale of the intermediate values, matches the reward index
+const ONE: i128 = 1_000_000_000_000_000_000;
+// ln(2) scaled by ONE
+const LN_2: i128 = 693_147_180_559_945_309;
+// the reward rate is expressed in 1e-8 units per tick
+const RATE_SCALE: i128 = ONE / 100_000_000;
+const BPS: i128 = 10_000;
+
+// 10_000% a year
+pub const MAX_APY_BPS: u32 = 1_000_000;
+
+fn mul(a: i128, b: i128) -> Option<i128> {
+    checked_mul_div(a, b, ONE, Rounding::Down)
+}
+
+fn div(a: i128, b: i128) -> Option<i128> {
+    checked_mul_div(a, ONE, b, Rounding::Down)
+}
+
+// natural logarithm of x >= ONE, halving x into [1, 2) and summing
+// ln(x) = 2 * atanh((x - 1) / (x + 1)) over the remainder
+fn ln(mut x: i128) -> Option<i128> {
+    let mut result = 0;
+    while x >= 2 * ONE {
+        x /= 2;
+        result += LN_2;
+    }
+    let y = div(x - ONE, x.checked_add(ONE)?)?;
+    let y2 = mul(y, y)?;
+    let (mut term, mut k, mut sum) = (y, 1, 0);
+    while term != 0 {
+        sum += term / k;
+        term = mul(term, y2)?;
+        k += 2;
+    }
+    Some(result + 2 * sum)
+}
+
+// e^x - 1 for x >= 0, `None` once it no longer fits
+fn exp_m1(x: i128) -> Option<i128> {
+    let (mut term, mut k, mut sum) = (x, 1, 0i128);
+    while term != 0 {
+        sum = sum.checked_add(term)?;
+        k += 1;
+        term = mul(term, x)? / k;
+    }
+    Some(sum)
+}
+
+/// Per-tick rate that yields `apy_bps` over a year when rewards compound
+/// once per tick, i.e. (1 + apy)^(tick / year) - 1, rounded to the nearest
+/// rate unit.
+pub fn apy_to_rate(apy_bps: u32, tick: u32, units_per_year: u32) -> Option<u32> {
+    if apy_bps > MAX_APY_BPS || tick == 0 {
+        return None;
+    }
+    let growth = ln(ONE + apy_bps as i128 * ONE / BPS)?;
+    let per_tick = exp_m1(checked_mul_div(
+        growth,
+        tick as i128,
+        units_per_year as i128,
+        Rounding::Down,
+    )?)?;
+    u32::try_from((per_tick + RATE_SCALE / 2) / RATE_SCALE).ok()
+}
+
+/// Annual yield in basis points of a per-tick rate compounded once per tick,
+/// rounded to the nearest basis point and capped at `u32::MAX`.
+pub fn rate_to_apy(rate: u32, tick: u32, units_per_year: u32) -> u32 {
+    if tick == 0 {
+        return 0;
+    }
+    compounded_apy(rate, tick, units_per_year)
+        .and_then(|apy| u32::try_from(apy).ok())
+        .unwrap_or(u32::MAX)
+}
+
+// `None` when the yield is too large to compute, which is well past u32
+fn compounded_apy(rate: u32, tick: u32, units_per_year: u32) -> Option<i128> {
+    let growth = ln(ONE + rate as i128 * RATE_SCALE)?;
+    let yearly = exp_m1(checked_mul_div(
+        growth,
+        units_per_year as i128,
+        tick as i128,
+        Rounding::Down,
+    )?)?;
+    Some(yearly.checked_mul(BPS)?.checked_add(ONE / 2)? / ONE)
+}
+
+pub fn check_apy_to_rate(e: &Env, apy_bps: u32, tick: u32) -> u32 {
+    match apy_to_rate(apy_bps, tick, units_per_year(e)) {
+        Some(rate) => rate,
+        None => panic_with_error!(e, ExcellarError::InvalidApy),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use soroban_sdk::{testutils::Address as _, Address, Env, IntoVal};
+
+    use crate::apy::{apy_to_rate, rate_to_apy, BPS, MAX_APY_BPS, ONE, RATE_SCALE};
+    use crate::contract::{ExcellarToken, ExcellarTokenClient};
+    use crate::error::ExcellarError;
+    use crate::storage_types::{AccrualClock, KycTier, SECONDS_PER_YEAR};
+    use crate::test::{set_sequence_number, set_timestamp};
+
+    // xorshift, enough to spread the sampled inputs
+    fn next(seed: &mut u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed
+    }
+
+    // ticks that divide the year so a whole number of periods makes a year
+    const PERIODS: [u32; 5] = [1, 4, 12, 73, 365];
+
+    #[test]
+    fn test_compounded_rate_realises_apy() {
+        let mut seed = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..200 {
+            let apy_bps = (next(&mut seed) % 50_000) as u32 + 1;
+            let periods = PERIODS[(next(&mut seed) % PERIODS.len() as u64) as usize];
+            let tick = SECONDS_PER_YEAR / periods;
+            let rate = apy_to_rate(apy_bps, tick, SECONDS_PER_YEAR).unwrap();
+
+            // compound a balance once per tick for a year
+            let mut balance: i128 = ONE;
+            for _ in 0..periods {
+                balance += balance * rate as i128 * RATE_SCALE / ONE;
+            }
+            let realised = (balance - ONE) * BPS / ONE;
+            assert!(
+                (realised - apy_bps as i128).abs() <= 1,
+                "apy {apy_bps} periods {periods} realised {realised}"
+            );
+            assert!(
+                (rate_to_apy(rate, tick, SECONDS_PER_YEAR) as i128 - apy_bps as i128).abs() <= 1
+            );
+        }
+    }
+
+    #[test]
+    fn test_apy_bounds() {
+        assert_eq!(apy_to_rate(0, 28_800, SECONDS_PER_YEAR), Some(0));
+        assert_eq!(rate_to_apy(0, 28_800, SECONDS_PER_YEAR), 0);
+        assert_eq!(
+            apy_to_rate(500, SECONDS_PER_YEAR, SECONDS_PER_YEAR),
+            Some(5_000_000)
+        );
+        assert_eq!(apy_to_rate(MAX_APY_BPS + 1, 1, SECONDS_PER_YEAR), None);
+        assert_eq!(apy_to_rate(500, 0, SECONDS_PER_YEAR), None);
+        // a rate past u32 cannot be configured
+        assert_eq!(
+            apy_to_rate(MAX_APY_BPS, SECONDS_PER_YEAR, SECONDS_PER_YEAR),
+            None
+        );
+        let month = SECONDS_PER_YEAR / 12;
+        let rate = apy_to_rate(MAX_APY_BPS, month, SECONDS_PER_YEAR).unwrap();
+        assert_eq!(rate_to_apy(rate, month, SECONDS_PER_YEAR), MAX_APY_BPS);
+
+        // yields past what the math can hold saturate instead of failing
+        assert_eq!(rate_to_apy(u32::MAX, 1, SECONDS_PER_YEAR), u32::MAX);
+        assert_eq!(rate_to_apy(100_000_000, 1, SECONDS_PER_YEAR), u32::MAX);
+        assert_eq!(apy_to_rate(MAX_APY_BPS, u32::MAX, 1), None);
+    }
+
+    #[test]
+    fn test_set_reward_apy_with_auto_compound() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = ExcellarTokenClient::new(&e, &e.register_contract(None, ExcellarToken {}));
+        let start = 1_700_000_000;
+        set_sequence_number(&e, 0);
+        set_timestamp(&e, start);
+        token.initialize(
+            &admin,
+            &7,
+            &"name".into_val(&e),
+            &"symbol".into_val(&e),
+            &AccrualClock::Timestamp,
+        );
+        assert_eq!(token.current_apy(), 500);
+        let month = SECONDS_PER_YEAR / 12;
+        token.set_reward_tick(&admin, &month);
+        token.set_reward_apy(&admin, &500);
+        assert_eq!(token.current_apy(), 500);
+        assert_eq!(
+            token.try_set_reward_apy(&admin, &(MAX_APY_BPS + 1)),
+            Err(Ok(ExcellarError::InvalidApy.into()))
+        );
+
+        let balance = 10_000_000_000;
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+        token.mint(&admin, &user, &balance);
+        token.set_auto_compound(&user, &true);
+        for i in 1..=12 {
+            set_timestamp(&e, start + (month * i) as u64);
+            token.transfer(&user, &user, &0);
+        }
+        // each payout rounds down, so the realised yield may fall short a little
+        let realised = (token.balance(&user) - balance) * BPS / balance;
+        assert!((realised - 500).abs() <= 1, "realised {realised}");
+
+        // scheduled changes that fell due are reflected right away
+        let next = start + SECONDS_PER_YEAR as u64 + 1;
+        token.schedule_reward_rate(&admin, &0, &month, &next);
+        assert_eq!(token.current_apy(), 500);
+        set_timestamp(&e, next);
+        assert_eq!(token.current_apy(), 0);
+    }
+}
@@ -1,11 +1,24 @@
-use soroban_sdk::{Address, Env};
+use soroban_sdk::{panic_with_error, Address, Env};
 
+use crate::error::ExcellarError;
+use crate::fixed_point::Rounding;
+use crate::holder::add_holder;
+use crate::rebase::{amount_to_shares, read_rebase_state, shares_to_amount, static_balance_target};
 use crate::storage_types::{
     DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT,
     INSTANCE_LIFETIME_THRESHOLD,
 };
 
 pub fn read_balance(e: &Env, addr: Address) -> i128 {
+    match read_rebase_state(e) {
+        Some(state) => {
+            shares_to_amount(e, &state, read_shares(e, addr.clone())) + read_static_balance(e, addr)
+        }
+        None => read_stored_balance(e, addr),
+    }
+}
+
+fn read_stored_balance(e: &Env, addr: Address) -> i128 {
     let key = DataKey::Balance(addr);
     if let Some(balance) = e.storage().persistent().get::<DataKey, i128>(&key) {
         e.storage()
@@ -25,22 +38,229 @@ fn write_balance(e: &Env, addr: Address, amount: i128) {
         .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
 }
 
+/// Shares held by the address; a balance not yet converted after the switch
+/// to rebasing counts one share per unit, as does any balance before it.
+pub fn read_shares(e: &Env, addr: Address) -> i128 {
+    let key = DataKey::Shares(addr.clone());
+    if let Some(shares) = e.storage().persistent().get::<DataKey, i128>(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        shares
+    } else {
+        read_stored_balance(e, addr)
+    }
+}
+
+fn write_shares(e: &Env, addr: Address, shares: i128) {
+    let key = DataKey::Shares(addr.clone());
+    e.storage().persistent().set(&key, &shares);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    e.storage().persistent().remove(&DataKey::Balance(addr));
+}
+
+fn add_shares(e: &Env, addr: Address, shares: i128) {
+    let total_shares = read_total_shares(e);
+    if total_shares + shares < 0 {
+        panic_with_error!(e, ExcellarError::InsufficientTotalSupply);
+    }
+    write_shares(e, addr.clone(), read_shares(e, addr) + shares);
+    write_total_shares(e, total_shares + shares);
+}
+
+/// Part of the balance held outside shares once rebasing, which does not
+/// grow.
+pub fn read_static_balance(e: &Env, addr: Address) -> i128 {
+    let key = DataKey::StaticBalance(addr);
+    if let Some(amount) = e.storage().persistent().get::<DataKey, i128>(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        amount
+    } else {
+        0
+    }
+}
+
+fn add_static_balance(e: &Env, addr: Address, amount: i128) {
+    let key = DataKey::StaticBalance(addr.clone());
+    let balance = read_static_balance(e, addr) + amount;
+    if balance == 0 {
+        e.storage().persistent().remove(&key);
+    } else {
+        e.storage().persistent().set(&key, &balance);
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    }
+
+    let key = DataKey::TotalStaticBalance;
+    let total = read_total_static_balance(e);
+    e.storage().persistent().set(&key, &(total + amount));
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+fn read_total_static_balance(e: &Env) -> i128 {
+    let key = DataKey::TotalStaticBalance;
+    if let Some(total) = e.storage().persistent().get::<DataKey, i128>(&key) {
+        e.storage().persistent().extend_ttl(
+            &key,
+            INSTANCE_LIFETIME_THRESHOLD,
+            INSTANCE_BUMP_AMOUNT,
+        );
+        total
+    } else {
+        0
+    }
+}
+
+/// Moves the balance of `addr` between shares and its static part so the
+/// static part matches what the address is entitled to right now. Rebasing
+/// balances follow eligibility from the moment this runs, which is on every
+/// reward checkpoint.
+pub fn sync_static_balance(e: &Env, addr: Address) {
+    let Some(state) = read_rebase_state(e) else {
+        return;
+    };
+    let current = read_static_balance(e, addr.clone());
+    let target = static_balance_target(e, addr.clone(), read_balance(e, addr.clone()));
+    if target > current {
+        // the address pays for the rounding either way
+        let shares = amount_to_shares(e, &state, target - current, Rounding::Up)
+            .min(read_shares(e, addr.clone()));
+        add_shares(e, addr.clone(), -shares);
+        add_static_balance(e, addr, target - current);
+    } else if target < current {
+        let shares = amount_to_shares(e, &state, current - target, Rounding::Down);
+        add_shares(e, addr.clone(), shares);
+        add_static_balance(e, addr, target - current);
+    }
+}
+
 pub fn receive_balance(e: &Env, addr: Address, amount: i128) {
-    let balance = read_balance(e, addr.clone());
-    write_balance(e, addr, balance + amount);
-    increase_total_supply(e, amount)
+    add_holder(e, addr.clone());
+    match read_rebase_state(e) {
+        Some(state) => {
+            // whatever the address does not grow on is kept static
+            let balance = read_balance(e, addr.clone()) + amount;
+            let to_static = (static_balance_target(e, addr.clone(), balance)
+                - read_static_balance(e, addr.clone()))
+            .clamp(0, amount);
+            add_static_balance(e, addr.clone(), to_static);
+            // shares round down so that receiving never adds to the supply
+            // more than `amount`
+            if amount > to_static {
+                let shares = amount_to_shares(e, &state, amount - to_static, Rounding::Down);
+                add_shares(e, addr, shares);
+            }
+        }
+        None => {
+            let balance = read_stored_balance(e, addr.clone());
+            write_balance(e, addr, balance + amount);
+            increase_total_supply(e, amount)
+        }
+    }
+}
+
+fn debit_balance(e: &Env, addr: Address, balance: i128, amount: i128) {
+    match read_rebase_state(e) {
+        Some(state) => {
+            // the static part only covers what stays static afterwards
+            let current = read_static_balance(e, addr.clone());
+            let target = static_balance_target(e, addr.clone(), balance - amount);
+            let from_static = (current - target).clamp(0, amount);
+            add_static_balance(e, addr.clone(), -from_static);
+            if amount > from_static {
+                let shares = amount_to_shares(e, &state, amount - from_static, Rounding::Up)
+                    .min(read_shares(e, addr.clone()));
+                add_shares(e, addr, -shares);
+            }
+        }
+        None => {
+            write_balance(e, addr, balance - amount);
+            decrease_total_supply(e, amount)
+        }
+    }
 }
 
 pub fn spend_balance(e: &Env, addr: Address, amount: i128) {
     let balance = read_balance(e, addr.clone());
     if balance < amount {
-        panic!("insufficient balance");
+        panic_with_error!(e, ExcellarError::InsufficientBalance);
+    }
+    if balance - amount < read_frozen_balance(e, addr.clone()) {
+        panic_with_error!(e, ExcellarError::BalanceFrozen);
+    }
+    debit_balance(e, addr, balance, amount)
+}
+
+// Enforcement actions may take frozen funds; the frozen amount shrinks with the balance.
+pub fn seize_balance(e: &Env, addr: Address, amount: i128) {
+    let balance = read_balance(e, addr.clone());
+    if balance < amount {
+        panic_with_error!(e, ExcellarError::InsufficientBalance);
+    }
+    let frozen = read_frozen_balance(e, addr.clone());
+    if frozen > balance - amount {
+        write_frozen_balance(e, addr.clone(), balance - amount);
+    }
+    debit_balance(e, addr, balance, amount)
+}
+
+pub fn read_frozen_balance(e: &Env, addr: Address) -> i128 {
+    let key = DataKey::FrozenBalance(addr);
+    if let Some(frozen) = e.storage().persistent().get::<DataKey, i128>(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        frozen
+    } else {
+        0
+    }
+}
+
+fn write_frozen_balance(e: &Env, addr: Address, amount: i128) {
+    let key = DataKey::FrozenBalance(addr);
+    if amount == 0 {
+        e.storage().persistent().remove(&key);
+        return;
+    }
+    e.storage().persistent().set(&key, &amount);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+pub fn freeze_balance(e: &Env, addr: Address, amount: i128) {
+    let frozen = read_frozen_balance(e, addr.clone());
+    if frozen + amount > read_balance(e, addr.clone()) {
+        panic_with_error!(e, ExcellarError::InsufficientBalance);
+    }
+    write_frozen_balance(e, addr, frozen + amount);
+}
+
+pub fn unfreeze_balance(e: &Env, addr: Address, amount: i128) {
+    let frozen = read_frozen_balance(e, addr.clone());
+    if frozen < amount {
+        panic_with_error!(e, ExcellarError::InsufficientFrozenBalance);
     }
-    write_balance(e, addr, balance - amount);
-    decrease_total_supply(e, amount)
+    write_frozen_balance(e, addr, frozen - amount);
 }
 
 pub fn total_supply(e: &Env) -> i128 {
+    match read_rebase_state(e) {
+        Some(state) => {
+            shares_to_amount(e, &state, read_total_shares(e)) + read_total_static_balance(e)
+        }
+        None => read_total_supply(e),
+    }
+}
+
+fn read_total_supply(e: &Env) -> i128 {
     let key = DataKey::TotalSupply;
     if let Some(total_supply) = e.storage().persistent().get::<DataKey, i128>(&key) {
         e.storage().persistent().extend_ttl(
@@ -62,14 +282,37 @@ fn write_total_supply(e: &Env, amount: i128) {
 }
 
 pub fn increase_total_supply(e: &Env, amount: i128) {
-    let total_supply = total_supply(e);
+    let total_supply = read_total_supply(e);
     write_total_supply(e, total_supply + amount);
 }
 
 pub fn decrease_total_supply(e: &Env, amount: i128) {
-    let total_supply = total_supply(e);
+    let total_supply = read_total_supply(e);
     if total_supply < amount {
-        panic!("Insufficient total supply");
+        panic_with_error!(e, ExcellarError::InsufficientTotalSupply);
     }
     write_total_supply(e, total_supply - amount);
 }
+
+/// Total shares outstanding; before the switch to rebasing this is the supply.
+pub fn read_total_shares(e: &Env) -> i128 {
+    let key = DataKey::TotalShares;
+    if let Some(total_shares) = e.storage().persistent().get::<DataKey, i128>(&key) {
+        e.storage().persistent().extend_ttl(
+            &key,
+            INSTANCE_LIFETIME_THRESHOLD,
+            INSTANCE_BUMP_AMOUNT,
+        );
+        total_shares
+    } else {
+        read_total_supply(e)
+    }
+}
+
+fn write_total_shares(e: &Env, amount: i128) {
+    let key = DataKey::TotalShares;
+    e.storage().persistent().set(&key, &amount);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
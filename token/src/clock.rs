@@ -0,0 +1,127 @@
+use soroban_sdk::Env;
+
+use crate::storage_types::{
+    AccrualClock, DataKey, DAY_IN_LEDGERS, INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD,
+    SECONDS_PER_YEAR,
+};
+
+pub fn read_accrual_clock(e: &Env) -> AccrualClock {
+    let key = DataKey::AccrualClock;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage()
+        .instance()
+        .get(&key)
+        .unwrap_or(AccrualClock::Ledger)
+}
+
+pub fn write_accrual_clock(e: &Env, clock: AccrualClock) {
+    let key = DataKey::AccrualClock;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().set(&key, &clock);
+}
+
+/// Current reading of the clock rewards accrue on, the ledger sequence or
+/// the ledger close time in seconds. Every point in time rewards record is
+/// read off it.
+pub fn accrual_now(e: &Env) -> u64 {
+    match read_accrual_clock(e) {
+        AccrualClock::Ledger => e.ledger().sequence().into(),
+        AccrualClock::Timestamp => e.ledger().timestamp(),
+    }
+}
+
+/// Clock units in a year, assuming five second ledgers on the ledger clock.
+pub fn units_per_year(e: &Env) -> u32 {
+    match read_accrual_clock(e) {
+        AccrualClock::Ledger => 365 * DAY_IN_LEDGERS,
+        AccrualClock::Timestamp => SECONDS_PER_YEAR,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use soroban_sdk::testutils::{Address as _, Ledger};
+    use soroban_sdk::{Address, Env, IntoVal};
+
+    use crate::contract::{ExcellarToken, ExcellarTokenClient};
+    use crate::storage_types::{AccrualClock, KycTier, SECONDS_PER_YEAR};
+    use crate::test::{set_sequence_number, set_timestamp};
+
+    #[test]
+    fn test_timestamp_accrual() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = ExcellarTokenClient::new(&e, &e.register_contract(None, ExcellarToken {}));
+        set_sequence_number(&e, 0);
+        set_timestamp(&e, 1_700_000_000);
+        token.initialize(
+            &admin,
+            &7,
+            &"name".into_val(&e),
+            &"symbol".into_val(&e),
+            &AccrualClock::Timestamp,
+        );
+        assert_eq!(token.accrual_clock(), AccrualClock::Timestamp);
+        // 10% per year
+        token.set_reward_rate(&admin, &10_000_000);
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+        token.mint(&admin, &user, &1000);
+
+        // ledgers closing faster or slower do not change what accrues
+        e.ledger().with_mut(|l| l.sequence_number = 1_000);
+        assert_eq!(token.pending_reward(&user), 0);
+        set_timestamp(&e, 1_700_000_000 + SECONDS_PER_YEAR as u64 / 2);
+        assert_eq!(token.pending_reward(&user), 50);
+        e.ledger().with_mut(|l| l.sequence_number = 50_000);
+        assert_eq!(token.pending_reward(&user), 50);
+
+        // scheduled changes take effect at a timestamp
+        let effective = 1_700_000_000 + SECONDS_PER_YEAR as u64;
+        token.schedule_reward_rate(&admin, &0, &SECONDS_PER_YEAR, &effective);
+        set_timestamp(&e, effective + SECONDS_PER_YEAR as u64);
+        token.claim_reward(&user);
+        assert_eq!(token.balance(&user), 1100);
+    }
+
+    #[test]
+    fn test_timestamp_accrual_past_u32() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = ExcellarTokenClient::new(&e, &e.register_contract(None, ExcellarToken {}));
+        let half_year = SECONDS_PER_YEAR as u64 / 2;
+        set_sequence_number(&e, 0);
+        set_timestamp(&e, u32::MAX as u64 - half_year);
+        token.initialize(
+            &admin,
+            &7,
+            &"name".into_val(&e),
+            &"symbol".into_val(&e),
+            &AccrualClock::Timestamp,
+        );
+        // 10% per year
+        token.set_reward_rate(&admin, &10_000_000);
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+        token.mint(&admin, &user, &1000);
+
+        // a year across 2106 still accrues a year
+        set_timestamp(&e, u32::MAX as u64 + half_year);
+        token.claim_reward(&user);
+        assert_eq!(token.balance(&user), 1100);
+        assert_eq!(
+            token.reward_info(&user).last_accrued_at,
+            u32::MAX as u64 + half_year
+        );
+    }
+}
@@ -1,42 +1,100 @@
 //! This contract demonstrates a sample implementation of the Soroban token
 //! interface.
 use soroban_sdk::token::{self, Interface as _};
-use soroban_sdk::{contract, contractimpl, Address, BytesN, Env, String};
+use soroban_sdk::{contract, contractimpl, panic_with_error, Address, BytesN, Env, String, Vec};
 use soroban_token_sdk::metadata::TokenMetadata;
 use soroban_token_sdk::TokenUtils;
 
 use crate::admin::{
-    add_amm, check_kyc_passed, check_not_amm, check_not_blacklisted, has_administrator, is_amm,
-    read_administrator, remove_amm, remove_blacklist, remove_kyc, require_admin,
-    write_administrator, write_blacklist, write_kyc,
+    add_amm, check_kyc_passed, check_local_kyc_used, check_not_amm, check_not_blacklisted,
+    has_administrator, is_amm, is_blacklisted, is_kyc_passed, read_administrator,
+    read_blacklist_reward_policy, read_kyc, read_kyc_revocation, read_kyc_revocation_reward_policy,
+    read_kyc_validity, read_pending_admin, remove_amm, remove_blacklist, remove_kyc,
+    remove_pending_admin, require_admin, write_administrator, write_blacklist,
+    write_blacklist_reward_policy, write_kyc, write_kyc_revocation,
+    write_kyc_revocation_reward_policy, write_kyc_validity, write_pending_admin,
 };
 use crate::allowance::{read_allowance, spend_allowance, write_allowance};
-use crate::amm::update_amm_depositor_balance;
-use crate::balance::{read_balance, receive_balance, spend_balance, total_supply};
+use crate::amm::{reduce_amm_depositors_pro_rata, update_amm_depositor_balance};
+use crate::apy::{check_apy_to_rate, rate_to_apy};
+use crate::balance::{
+    freeze_balance, read_balance, read_frozen_balance, read_shares, read_total_shares,
+    receive_balance, seize_balance, spend_balance, sync_static_balance, total_supply,
+    unfreeze_balance,
+};
+use crate::clock::{accrual_now, read_accrual_clock, units_per_year, write_accrual_clock};
+use crate::eligibility::{
+    is_reward_eligible, is_reward_excluded, is_reward_opted_out, read_forgone_reward_treasury,
+    write_forgone_reward_treasury, write_reward_excluded, write_reward_opt_out,
+};
+use crate::error::ExcellarError;
 use crate::event::{
-    add_amm_event, blacklist_event, fail_kyc_event, pass_kyc_event, remove_amm_event,
-    whitelist_event,
+    add_amm_event, auto_compound_event, blacklist_event, blacklist_reward_policy_event,
+    cancel_admin_proposal_event, cancel_reward_rate_event, clawback_event,
+    default_auto_compound_event, fail_kyc_event, force_transfer_event, forfeit_reward_event,
+    forgone_reward_treasury_event, freeze_event, freeze_reward_event, frozen_reward_accrual_event,
+    fund_reward_reserve_event, identity_registry_event, kyc_revocation_reward_policy_event,
+    kyc_validity_event, pass_kyc_event, pause_event, propose_admin_event, rebasing_event,
+    remove_amm_event, reward_asset_event, reward_claimer_event, reward_exclusion_event,
+    reward_funding_event, reward_opt_out_event, role_admin_changed_event, role_granted_event,
+    role_revoked_event, schedule_reward_rate_event, shortfall_policy_event, tier_policy_event,
+    unfreeze_event, unpause_event, whitelist_event, withdraw_reward_reserve_event,
+};
+use crate::funding::{
+    affordable_reward, available_reward_funds, can_receive_reward, pay_reward, payable_reward,
+    read_reward_asset, read_reward_funding, read_reward_reserve, read_shortfall_policy,
+    write_reward_asset, write_reward_funding, write_shortfall_policy,
 };
+use crate::holder::{holds_nothing, read_holder, read_holder_count, remove_holder, MAX_BATCH_SIZE};
 use crate::metadata::{read_decimal, read_name, read_symbol, write_metadata};
+use crate::pause::{check_not_paused, is_paused, write_paused};
+use crate::rebase::{is_rebasing, start_rebasing};
+use crate::registry::{read_identity_registry, write_identity_registry};
 use crate::reward::{
-    checkpoint_reward, read_reward, reset_reward, set_reward_rate, set_reward_tick,
+    checkpoint_reward, current_reward_rate, deduct_reward, forfeit_reward_to_treasury,
+    get_default_auto_compound, get_frozen_reward_accrual, is_auto_compound, read_reward,
+    read_reward_claimer, read_reward_info, reset_reward, restart_reward_accrual,
+    set_default_auto_compound, set_frozen_reward_accrual, set_reward_rate, set_reward_tick,
+    update_reward_index, write_auto_compound, write_reward_claimer,
+};
+use crate::role::{
+    has_role, read_role_admin, remove_role, require_role, write_role, write_role_admin,
+};
+use crate::schedule::{
+    cancel_rate_change, read_rate_history, read_rate_schedule, schedule_rate_change,
+};
+use crate::storage_types::{
+    AccrualClock, BlacklistRewardPolicy, IdentityRegistryConfig, KycRecord,
+    KycRevocationRewardPolicy, KycTier, PauseScope, PendingAdmin, RateChange, RewardAsset,
+    RewardFunding, RewardInfo, Role, ShortfallPolicy, TierPolicy, INSTANCE_BUMP_AMOUNT,
+    INSTANCE_LIFETIME_THRESHOLD, SECONDS_PER_YEAR,
 };
 #[cfg(test)]
 use crate::storage_types::{AllowanceDataKey, AllowanceValue, DataKey};
-use crate::storage_types::{INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD};
+use crate::tier::{
+    check_balance_limit, check_claim_allowed, check_outflow_limit, check_transfer_limits,
+    is_claim_allowed, read_tier_policy, write_tier_policy,
+};
 
 #[contract]
 pub struct ExcellarToken;
 
 #[contractimpl]
 impl ExcellarToken {
-    pub fn initialize(e: Env, admin: Address, decimal: u32, name: String, symbol: String) {
+    pub fn initialize(
+        e: Env,
+        admin: Address,
+        decimal: u32,
+        name: String,
+        symbol: String,
+        clock: AccrualClock,
+    ) {
         if has_administrator(&e) {
-            panic!("already initialized")
+            panic_with_error!(&e, ExcellarError::AlreadyInitialized)
         }
         write_administrator(&e, &admin);
         if decimal > u8::MAX.into() {
-            panic!("Decimal must fit in a u8");
+            panic_with_error!(&e, ExcellarError::DecimalTooLarge);
         }
 
         write_metadata(
@@ -48,95 +106,634 @@ impl ExcellarToken {
             },
         );
 
-        // should be roughly 0.013% to result in 5% APY. Below is 0.01%
-        set_reward_rate(&e, 10_000);
-        // roughly the number of ledger advancements in day
-        set_reward_tick(&e, 28_800);
+        write_accrual_clock(&e, clock);
+        match clock {
+            AccrualClock::Ledger => {
+                // should be roughly 0.013% to result in 5% APY. Below is 0.01%
+                set_reward_rate(&e, 10_000);
+                // roughly the number of ledger advancements in day
+                set_reward_tick(&e, 28_800);
+            }
+            AccrualClock::Timestamp => {
+                // 5% APY
+                set_reward_rate(&e, 5_000_000);
+                set_reward_tick(&e, SECONDS_PER_YEAR);
+            }
+        }
     }
 
-    pub fn mint(e: Env, to: Address, amount: i128) {
-        pre_mint_burn_checks(&e, to.clone(), amount);
-        let admin = require_admin(&e);
+    pub fn accrual_clock(e: Env) -> AccrualClock {
+        read_accrual_clock(&e)
+    }
+
+    pub fn mint(e: Env, minter: Address, to: Address, amount: i128) {
+        pre_mint_burn_checks(&e, PauseScope::Mint, to.clone(), amount);
+        require_role(&e, Role::Minter, minter.clone());
+        check_balance_limit(&e, to.clone(), amount);
 
         checkpoint_reward(&e, to.clone());
         receive_balance(&e, to.clone(), amount);
-        TokenUtils::new(&e).events().mint(admin, to, amount);
+        TokenUtils::new(&e).events().mint(minter, to, amount);
     }
 
     pub fn claim_reward(e: Env, to: Address) {
         to.require_auth();
+        check_not_paused(&e, PauseScope::Claim);
         check_kyc_passed(&e, to.clone());
+        check_not_blacklisted(&e, to.clone());
+        check_claim_allowed(&e, to.clone());
         // amm addresses cannot directly claim
         check_not_amm(&e, to.clone());
 
         checkpoint_reward(&e, to.clone());
-        let reward = read_reward(&e, to.clone());
+        let reward = payable_reward(&e, read_reward(&e, to.clone()));
         if reward < 1 {
             return;
         }
-        reset_reward(&e, to.clone());
-        receive_balance(&e, to.clone(), reward);
-        TokenUtils::new(&e)
-            .events()
-            .mint(to.clone(), to.clone(), reward);
+        let paid = pay_reward(&e, to.clone(), to.clone(), reward);
+        deduct_reward(&e, to, paid);
     }
 
-    pub fn admin_claim_reward(e: Env, to: Address) {
-        let admin = require_admin(&e);
+    /// Pays out the rewards of up to `MAX_BATCH_SIZE` addresses, skipping any
+    /// that cannot claim instead of failing the batch.
+    pub fn admin_claim_rewards(e: Env, manager: Address, addresses: Vec<Address>) {
+        require_role(&e, Role::RewardManager, manager.clone());
+        check_not_paused(&e, PauseScope::Claim);
+        if addresses.len() > MAX_BATCH_SIZE {
+            panic_with_error!(&e, ExcellarError::BatchTooLarge)
+        }
+
+        for to in addresses.iter() {
+            distribute_reward(&e, manager.clone(), to);
+        }
+    }
+
+    /// Pays out the rewards of up to `limit` holders from the holder index,
+    /// starting at `cursor`, capped at `MAX_BATCH_SIZE` per call. Holders
+    /// left with no balance and no reward are dropped from the index, the
+    /// last holder moving into their place. Returns the cursor to continue
+    /// from, `None` once every holder was visited.
+    pub fn distribute_rewards(e: Env, manager: Address, cursor: u32, limit: u32) -> Option<u32> {
+        require_role(&e, Role::RewardManager, manager.clone());
+        check_not_paused(&e, PauseScope::Claim);
+
+        let mut position = cursor;
+        for _ in 0..limit.min(MAX_BATCH_SIZE) {
+            if position >= read_holder_count(&e) {
+                break;
+            }
+            let holder = read_holder(&e, position);
+            distribute_reward(&e, manager.clone(), holder.clone());
+            if holds_nothing(&e, holder) {
+                remove_holder(&e, position);
+            } else {
+                position += 1;
+            }
+        }
+        if position < read_holder_count(&e) {
+            Some(position)
+        } else {
+            None
+        }
+    }
+
+    pub fn holder_count(e: Env) -> u32 {
+        read_holder_count(&e)
+    }
+
+    /// Claims the reward of `owner` into `recipient`. Needs the auth of the
+    /// claimer `owner` delegated to, or of `owner` when there is none; both
+    /// have to pass KYC, the remaining checks apply to the recipient.
+    pub fn claim_reward_to(e: Env, owner: Address, recipient: Address) {
+        match read_reward_claimer(&e, owner.clone()) {
+            Some(claimer) => claimer.require_auth(),
+            None => owner.require_auth(),
+        }
+        check_not_paused(&e, PauseScope::Claim);
+        check_kyc_passed(&e, owner.clone());
+        check_not_blacklisted(&e, owner.clone());
+        check_claim_allowed(&e, owner.clone());
+        check_not_amm(&e, owner.clone());
+        check_kyc_passed(&e, recipient.clone());
+        check_not_blacklisted(&e, recipient.clone());
+        check_claim_allowed(&e, recipient.clone());
+        check_not_amm(&e, recipient.clone());
+
+        checkpoint_reward(&e, owner.clone());
+        let reward = payable_reward(&e, read_reward(&e, owner.clone()));
+        if reward < 1 {
+            return;
+        }
+        checkpoint_reward(&e, recipient.clone());
+        let paid = pay_reward(&e, owner.clone(), recipient, reward);
+        deduct_reward(&e, owner, paid);
+    }
+
+    /// Lets `claimer` claim the rewards of `owner` to any eligible recipient,
+    /// `None` revokes the delegation.
+    pub fn set_reward_claimer(e: Env, owner: Address, claimer: Option<Address>) {
+        owner.require_auth();
+
+        write_reward_claimer(&e, owner.clone(), claimer.clone());
+        reward_claimer_event(&e, owner, claimer);
+    }
+
+    pub fn reward_claimer(e: Env, owner: Address) -> Option<Address> {
+        read_reward_claimer(&e, owner)
+    }
+
+    pub fn admin_claim_reward(e: Env, manager: Address, to: Address) {
+        require_role(&e, Role::RewardManager, manager.clone());
+        check_not_paused(&e, PauseScope::Claim);
         check_kyc_passed(&e, to.clone());
+        check_not_blacklisted(&e, to.clone());
+        check_claim_allowed(&e, to.clone());
         // amm addresses cannot be awarded directly
         check_not_amm(&e, to.clone());
 
         checkpoint_reward(&e, to.clone());
-        let reward = read_reward(&e, to.clone());
+        let reward = payable_reward(&e, read_reward(&e, to.clone()));
         if reward < 1 {
             return;
         }
-        reset_reward(&e, to.clone());
+        let paid = pay_reward(&e, manager, to.clone(), reward);
+        deduct_reward(&e, to, paid);
+    }
+
+    pub fn clawback(e: Env, operator: Address, from: Address, amount: i128, reason: u32) {
+        require_role(&e, Role::Compliance, operator.clone());
+        check_non_negative_amount(&e, amount);
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        checkpoint_reward(&e, from.clone());
+
+        if is_amm(&e, from.clone()) {
+            reduce_amm_depositors_pro_rata(&e, from.clone(), amount);
+        }
+
+        seize_balance(&e, from.clone(), amount);
+        TokenUtils::new(&e)
+            .events()
+            .clawback(operator.clone(), from.clone(), amount);
+        clawback_event(&e, operator, from, amount, reason);
+    }
+
+    pub fn force_transfer(
+        e: Env,
+        operator: Address,
+        from: Address,
+        to: Address,
+        amount: i128,
+        reason: u32,
+    ) {
+        require_role(&e, Role::Compliance, operator.clone());
+        check_non_negative_amount(&e, amount);
+        check_kyc_passed(&e, to.clone());
+        check_not_blacklisted(&e, to.clone());
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        checkpoint_reward(&e, from.clone());
+        checkpoint_reward(&e, to.clone());
+
+        if is_amm(&e, to.clone()) {
+            update_amm_depositor_balance(&e, to.clone(), from.clone(), amount);
+        } else if is_amm(&e, from.clone()) {
+            update_amm_depositor_balance(&e, from.clone(), to.clone(), -amount);
+        }
+
+        seize_balance(&e, from.clone(), amount);
+        receive_balance(&e, to.clone(), amount);
+        TokenUtils::new(&e)
+            .events()
+            .transfer(from.clone(), to.clone(), amount);
+        force_transfer_event(&e, operator, from, to, amount, reason);
+    }
+
+    pub fn freeze_amount(e: Env, operator: Address, addr: Address, amount: i128) {
+        require_role(&e, Role::Compliance, operator);
+        check_non_negative_amount(&e, amount);
+
+        checkpoint_reward(&e, addr.clone());
+        freeze_balance(&e, addr.clone(), amount);
+        sync_static_balance(&e, addr.clone());
+        freeze_event(&e, addr, amount);
+    }
+
+    pub fn unfreeze_amount(e: Env, operator: Address, addr: Address, amount: i128) {
+        require_role(&e, Role::Compliance, operator);
+        check_non_negative_amount(&e, amount);
+
+        checkpoint_reward(&e, addr.clone());
+        unfreeze_balance(&e, addr.clone(), amount);
+        sync_static_balance(&e, addr.clone());
+        unfreeze_event(&e, addr, amount);
+    }
+
+    pub fn frozen_balance(e: Env, addr: Address) -> i128 {
+        read_frozen_balance(&e, addr)
+    }
+
+    pub fn available_balance(e: Env, addr: Address) -> i128 {
+        read_balance(&e, addr.clone()) - read_frozen_balance(&e, addr)
+    }
+
+    pub fn set_frozen_reward_accrual(e: Env, manager: Address, enabled: bool) {
+        require_role(&e, Role::RewardManager, manager);
+
+        set_frozen_reward_accrual(&e, enabled);
+        frozen_reward_accrual_event(&e, enabled);
+    }
+
+    pub fn frozen_reward_accrual(e: Env) -> bool {
+        get_frozen_reward_accrual(&e)
+    }
+
+    pub fn set_auto_compound(e: Env, addr: Address, enabled: bool) {
+        addr.require_auth();
+
+        write_auto_compound(&e, addr.clone(), enabled);
+        // rewards already accrued compound right away
+        checkpoint_reward(&e, addr.clone());
+        auto_compound_event(&e, addr, enabled);
+    }
+
+    pub fn auto_compound(e: Env, addr: Address) -> bool {
+        is_auto_compound(&e, addr)
+    }
+
+    pub fn set_default_auto_compound(e: Env, manager: Address, enabled: bool) {
+        require_role(&e, Role::RewardManager, manager);
+
+        set_default_auto_compound(&e, enabled);
+        default_auto_compound_event(&e, enabled);
+    }
+
+    pub fn default_auto_compound(e: Env) -> bool {
+        get_default_auto_compound(&e)
+    }
+
+    /// Switches to rebasing: balances of addresses that earn rewards grow
+    /// with the reward index instead of accruing claimable rewards. The
+    /// growth is newly issued, `total_supply` rises without mint events, so
+    /// rewards have to be minted. Pools and addresses that do not earn
+    /// rewards keep a static balance, as does a frozen amount while frozen
+    /// balances do not accrue; a change in eligibility applies from the next
+    /// checkpoint of the address, `distribute_rewards` checkpoints them all.
+    pub fn enable_rebasing(e: Env, manager: Address) {
+        require_role(&e, Role::RewardManager, manager);
+
+        if is_rebasing(&e) {
+            panic_with_error!(&e, ExcellarError::AlreadyRebasing)
+        }
+        if read_reward_funding(&e) != RewardFunding::Mint || read_reward_asset(&e).is_some() {
+            panic_with_error!(&e, ExcellarError::RebasingRequiresMinting)
+        }
+
+        start_rebasing(&e);
+        rebasing_event(&e, read_total_shares(&e));
+    }
+
+    pub fn rebasing(e: Env) -> bool {
+        is_rebasing(&e)
+    }
+
+    pub fn shares_of(e: Env, addr: Address) -> i128 {
+        read_shares(&e, addr)
+    }
+
+    pub fn total_shares(e: Env) -> i128 {
+        read_total_shares(&e)
+    }
+
+    pub fn set_reward_funding(e: Env, manager: Address, funding: RewardFunding) {
+        require_role(&e, Role::RewardManager, manager.clone());
+        if is_rebasing(&e) && funding != RewardFunding::Mint {
+            panic_with_error!(&e, ExcellarError::RebasingRequiresMinting)
+        }
+        write_reward_funding(&e, funding.clone());
+        reward_funding_event(&e, manager, funding);
+    }
+
+    pub fn reward_funding(e: Env) -> RewardFunding {
+        read_reward_funding(&e)
+    }
+
+    /// Pays rewards in `asset` out of its vault instead of this token, `None`
+    /// goes back to the configured funding mode.
+    pub fn set_reward_asset(e: Env, manager: Address, asset: Option<RewardAsset>) {
+        require_role(&e, Role::RewardManager, manager.clone());
+        if is_rebasing(&e) && asset.is_some() {
+            panic_with_error!(&e, ExcellarError::RebasingRequiresMinting)
+        }
+        write_reward_asset(&e, asset.clone());
+        reward_asset_event(&e, manager, asset);
+    }
+
+    pub fn reward_asset(e: Env) -> Option<RewardAsset> {
+        read_reward_asset(&e)
+    }
+
+    pub fn set_reward_shortfall_policy(e: Env, manager: Address, policy: ShortfallPolicy) {
+        require_role(&e, Role::RewardManager, manager.clone());
+        write_shortfall_policy(&e, policy);
+        shortfall_policy_event(&e, manager, policy);
+    }
+
+    pub fn reward_shortfall_policy(e: Env) -> ShortfallPolicy {
+        read_shortfall_policy(&e)
+    }
+
+    /// Moves tokens from `from` into the reserve rewards are paid from in
+    /// `RewardFunding::Reserve` mode.
+    pub fn fund_reward_reserve(e: Env, from: Address, amount: i128) {
+        from.require_auth();
+        check_not_paused(&e, PauseScope::Transfer);
+        check_non_negative_amount(&e, amount);
+        check_not_blacklisted(&e, from.clone());
+        check_kyc_passed(&e, from.clone());
+        let contract = e.current_contract_address();
+        check_transfer_limits(&e, from.clone(), contract.clone(), amount);
+
+        checkpoint_reward(&e, from.clone());
+        if is_amm(&e, from.clone()) {
+            reduce_amm_depositors_pro_rata(&e, from.clone(), amount);
+        }
+
+        spend_balance(&e, from.clone(), amount);
+        receive_balance(&e, contract, amount);
+        fund_reward_reserve_event(&e, from, amount);
+    }
+
+    pub fn withdraw_reward_reserve(e: Env, manager: Address, to: Address, amount: i128) {
+        require_role(&e, Role::RewardManager, manager);
+        check_not_paused(&e, PauseScope::Transfer);
+        check_non_negative_amount(&e, amount);
+        check_kyc_passed(&e, to.clone());
+        check_not_blacklisted(&e, to.clone());
+
         checkpoint_reward(&e, to.clone());
-        receive_balance(&e, to.clone(), reward);
-        TokenUtils::new(&e).events().mint(admin, to, reward);
+        spend_balance(&e, e.current_contract_address(), amount);
+        receive_balance(&e, to.clone(), amount);
+        withdraw_reward_reserve_event(&e, to, amount);
+    }
+
+    pub fn reward_reserve(e: Env) -> i128 {
+        read_reward_reserve(&e)
     }
 
-    pub fn set_admin(e: Env, new_admin: Address) {
+    /// What the funding source can pay out right now, `i128::MAX` when
+    /// rewards are minted.
+    pub fn available_reward_funds(e: Env) -> i128 {
+        available_reward_funds(&e)
+    }
+
+    pub fn propose_admin(e: Env, new_admin: Address, expiration_ledger: u32) {
         let admin = require_admin(&e);
 
-        write_administrator(&e, &new_admin);
-        TokenUtils::new(&e).events().set_admin(admin, new_admin);
+        write_pending_admin(&e, &new_admin, expiration_ledger);
+        propose_admin_event(&e, admin, new_admin, expiration_ledger);
     }
 
-    pub fn fail_kyc(e: Env, addr: Address) {
+    pub fn accept_admin(e: Env) {
+        let pending = match read_pending_admin(&e) {
+            Some(pending) => pending,
+            None => panic_with_error!(&e, ExcellarError::NoPendingAdmin),
+        };
+        if pending.expiration_ledger < e.ledger().sequence() {
+            panic_with_error!(&e, ExcellarError::AdminProposalExpired)
+        }
+        pending.new_admin.require_auth();
+
         let admin = read_administrator(&e);
-        admin.require_auth();
+        write_administrator(&e, &pending.new_admin);
+        remove_pending_admin(&e);
+        TokenUtils::new(&e)
+            .events()
+            .set_admin(admin, pending.new_admin);
+    }
+
+    pub fn cancel_admin_proposal(e: Env) {
+        let admin = require_admin(&e);
+        let pending = match read_pending_admin(&e) {
+            Some(pending) => pending,
+            None => panic_with_error!(&e, ExcellarError::NoPendingAdmin),
+        };
+
+        remove_pending_admin(&e);
+        cancel_admin_proposal_event(&e, admin, pending.new_admin);
+    }
+
+    pub fn pending_admin(e: Env) -> Option<PendingAdmin> {
+        read_pending_admin(&e)
+    }
 
+    pub fn fail_kyc(e: Env, operator: Address, addr: Address) {
+        require_role(&e, Role::Compliance, operator);
+        check_local_kyc_used(&e);
+
+        let policy = read_kyc_revocation_reward_policy(&e);
+        // settle while the address still qualifies
+        checkpoint_reward(&e, addr.clone());
+        write_kyc_revocation(&e, addr.clone(), Some(policy));
         remove_kyc(&e, addr.clone());
+        match policy {
+            KycRevocationRewardPolicy::Keep => {}
+            KycRevocationRewardPolicy::Freeze => {
+                freeze_reward_event(&e, addr.clone(), read_reward(&e, addr.clone()));
+            }
+            KycRevocationRewardPolicy::ForfeitToTreasury => {
+                let reward = forfeit_reward_to_treasury(&e, addr.clone());
+                forfeit_reward_event(&e, addr.clone(), reward);
+            }
+        }
+        sync_static_balance(&e, addr.clone());
         fail_kyc_event(&e, addr.clone());
     }
 
-    pub fn pass_kyc(e: Env, addr: Address) {
-        let admin = read_administrator(&e);
-        admin.require_auth();
+    pub fn pass_kyc(e: Env, operator: Address, addr: Address, tier: KycTier) {
+        require_role(&e, Role::Compliance, operator);
+        check_local_kyc_used(&e);
 
-        write_kyc(&e, addr.clone());
-        pass_kyc_event(&e, addr.clone());
+        // nothing accrued while the address was unverified, unless its KYC
+        // was revoked under a policy that keeps accruing
+        let accruing = is_kyc_passed(&e, addr.clone())
+            || read_kyc_revocation(&e, addr.clone()) == Some(KycRevocationRewardPolicy::Keep);
+        write_kyc(&e, addr.clone(), tier);
+        write_kyc_revocation(&e, addr.clone(), None);
+        if !accruing {
+            restart_reward_accrual(&e, addr.clone());
+        }
+        sync_static_balance(&e, addr.clone());
+        pass_kyc_event(&e, addr.clone(), tier);
     }
 
-    pub fn blacklist(e: Env, addr: Address) {
-        let admin = read_administrator(&e);
-        admin.require_auth();
+    pub fn set_kyc_revocation_reward_policy(
+        e: Env,
+        operator: Address,
+        policy: KycRevocationRewardPolicy,
+    ) {
+        require_role(&e, Role::Compliance, operator);
+        // forfeited rewards would otherwise be dropped
+        if policy == KycRevocationRewardPolicy::ForfeitToTreasury
+            && read_forgone_reward_treasury(&e).is_none()
+        {
+            panic_with_error!(&e, ExcellarError::ForgoneRewardTreasuryNotSet)
+        }
+
+        write_kyc_revocation_reward_policy(&e, policy);
+        kyc_revocation_reward_policy_event(&e, policy);
+    }
+
+    pub fn kyc_revocation_reward_policy(e: Env) -> KycRevocationRewardPolicy {
+        read_kyc_revocation_reward_policy(&e)
+    }
+
+    pub fn kyc_status(e: Env, addr: Address) -> Option<KycRecord> {
+        read_kyc(&e, addr)
+    }
+
+    pub fn set_kyc_validity(e: Env, operator: Address, ledgers: u32) {
+        require_role(&e, Role::Compliance, operator);
+
+        write_kyc_validity(&e, ledgers);
+        kyc_validity_event(&e, ledgers);
+    }
+
+    pub fn kyc_validity(e: Env) -> u32 {
+        read_kyc_validity(&e)
+    }
+
+    pub fn set_identity_registry(
+        e: Env,
+        operator: Address,
+        config: Option<IdentityRegistryConfig>,
+    ) {
+        require_role(&e, Role::Compliance, operator);
+
+        write_identity_registry(&e, config.clone());
+        identity_registry_event(&e, config);
+    }
+
+    pub fn identity_registry(e: Env) -> Option<IdentityRegistryConfig> {
+        read_identity_registry(&e)
+    }
+
+    pub fn set_tier_policy(e: Env, operator: Address, tier: KycTier, policy: TierPolicy) {
+        require_role(&e, Role::Compliance, operator);
 
+        write_tier_policy(&e, tier, policy);
+        tier_policy_event(&e, tier);
+    }
+
+    pub fn tier_policy(e: Env, tier: KycTier) -> TierPolicy {
+        read_tier_policy(&e, tier)
+    }
+
+    pub fn blacklist(e: Env, operator: Address, addr: Address) {
+        require_role(&e, Role::Compliance, operator);
+
+        checkpoint_reward(&e, addr.clone());
         write_blacklist(&e, addr.clone());
+        if read_blacklist_reward_policy(&e) == BlacklistRewardPolicy::Forfeit {
+            let reward = read_reward(&e, addr.clone());
+            reset_reward(&e, addr.clone());
+            forfeit_reward_event(&e, addr.clone(), reward);
+        }
+        sync_static_balance(&e, addr.clone());
         blacklist_event(&e, addr.clone());
     }
 
-    pub fn whitelist(e: Env, addr: Address) {
-        let admin = read_administrator(&e);
-        admin.require_auth();
+    pub fn whitelist(e: Env, operator: Address, addr: Address) {
+        require_role(&e, Role::Compliance, operator);
 
-        remove_blacklist(&e, addr.clone());
+        if is_blacklisted(&e, addr.clone()) {
+            remove_blacklist(&e, addr.clone());
+            // only the registry can lift its own blacklisting
+            check_not_blacklisted(&e, addr.clone());
+            // the blacklisted period does not earn rewards
+            restart_reward_accrual(&e, addr.clone());
+        }
+        sync_static_balance(&e, addr.clone());
         whitelist_event(&e, addr.clone());
     }
 
+    /// Stops `addr` from earning rewards, what it earned so far stays
+    /// claimable.
+    pub fn exclude_from_rewards(e: Env, manager: Address, addr: Address) {
+        require_role(&e, Role::RewardManager, manager);
+
+        checkpoint_reward(&e, addr.clone());
+        write_reward_excluded(&e, addr.clone(), true);
+        sync_static_balance(&e, addr.clone());
+        reward_exclusion_event(&e, addr, true);
+    }
+
+    pub fn include_in_rewards(e: Env, manager: Address, addr: Address) {
+        require_role(&e, Role::RewardManager, manager);
+
+        // settles the excluded period before accrual resumes
+        checkpoint_reward(&e, addr.clone());
+        write_reward_excluded(&e, addr.clone(), false);
+        sync_static_balance(&e, addr.clone());
+        reward_exclusion_event(&e, addr, false);
+    }
+
+    /// Lets a holder stop, or resume, earning rewards on its own balance.
+    pub fn set_reward_opt_out(e: Env, addr: Address, opted_out: bool) {
+        addr.require_auth();
+
+        checkpoint_reward(&e, addr.clone());
+        write_reward_opt_out(&e, addr.clone(), opted_out);
+        sync_static_balance(&e, addr.clone());
+        reward_opt_out_event(&e, addr, opted_out);
+    }
+
+    pub fn reward_eligible(e: Env, addr: Address) -> bool {
+        is_reward_eligible(&e, addr)
+    }
+
+    pub fn reward_excluded(e: Env, addr: Address) -> bool {
+        is_reward_excluded(&e, addr)
+    }
+
+    pub fn reward_opted_out(e: Env, addr: Address) -> bool {
+        is_reward_opted_out(&e, addr)
+    }
+
+    /// Credits the yield ineligible addresses would have earned to
+    /// `treasury`, `None` lets it go unpaid.
+    pub fn set_forgone_reward_treasury(e: Env, manager: Address, treasury: Option<Address>) {
+        require_role(&e, Role::RewardManager, manager.clone());
+        if treasury.is_none()
+            && read_kyc_revocation_reward_policy(&e) == KycRevocationRewardPolicy::ForfeitToTreasury
+        {
+            panic_with_error!(&e, ExcellarError::ForgoneRewardTreasuryNotSet)
+        }
+        write_forgone_reward_treasury(&e, treasury.clone());
+        forgone_reward_treasury_event(&e, manager, treasury);
+    }
+
+    pub fn forgone_reward_treasury(e: Env) -> Option<Address> {
+        read_forgone_reward_treasury(&e)
+    }
+
+    pub fn set_blacklist_reward_policy(e: Env, operator: Address, policy: BlacklistRewardPolicy) {
+        require_role(&e, Role::Compliance, operator);
+
+        write_blacklist_reward_policy(&e, policy);
+        blacklist_reward_policy_event(&e, policy);
+    }
+
+    pub fn blacklist_reward_policy(e: Env) -> BlacklistRewardPolicy {
+        read_blacklist_reward_policy(&e)
+    }
+
     #[cfg(test)]
     pub fn get_allowance(e: Env, from: Address, spender: Address) -> Option<AllowanceValue> {
         let key = DataKey::Allowance(AllowanceDataKey { from, spender });
@@ -154,31 +751,85 @@ impl ExcellarToken {
         total_supply(&e)
     }
 
-    pub fn set_reward_rate(e: Env, rate: u32) {
-        require_admin(&e);
+    pub fn set_reward_rate(e: Env, manager: Address, rate: u32) {
+        require_role(&e, Role::RewardManager, manager);
         set_reward_rate(&e, rate);
     }
 
-    pub fn set_reward_tick(e: Env, rate: u32) {
-        require_admin(&e);
-        set_reward_tick(&e, rate);
+    /// Sets the rate from an annual yield in basis points, assuming rewards
+    /// compound once per tick. Changing the tick afterwards changes the yield.
+    /// Holders who claim instead of compounding earn simple interest on their
+    /// balance, `rate * year / tick`, which falls short of it.
+    pub fn set_reward_apy(e: Env, manager: Address, apy_bps: u32) {
+        require_role(&e, Role::RewardManager, manager);
+        let tick = current_reward_rate(&e).1;
+        set_reward_rate(&e, check_apy_to_rate(&e, apy_bps, tick));
     }
 
-    pub fn add_amm_address(e: Env, addr: Address) {
-        let admin = read_administrator(&e);
-        admin.require_auth();
+    /// Annual yield in basis points of the rate in force, compounded once
+    /// per tick and capped at `u32::MAX`. Only auto-compounding balances
+    /// realise it, claimed rewards earn simple interest.
+    pub fn current_apy(e: Env) -> u32 {
+        let (rate, tick) = current_reward_rate(&e);
+        rate_to_apy(rate, tick, units_per_year(&e))
+    }
+
+    pub fn set_reward_tick(e: Env, manager: Address, tick: u32) {
+        require_role(&e, Role::RewardManager, manager);
+        set_reward_tick(&e, tick);
+    }
+
+    pub fn schedule_reward_rate(e: Env, manager: Address, rate: u32, tick: u32, effective_at: u64) {
+        require_role(&e, Role::RewardManager, manager);
+
+        update_reward_index(&e);
+        let change = RateChange {
+            effective_at,
+            rate,
+            tick,
+        };
+        schedule_rate_change(&e, change.clone());
+        schedule_reward_rate_event(&e, change);
+    }
+
+    pub fn cancel_reward_rate(e: Env, manager: Address, effective_at: u64) {
+        require_role(&e, Role::RewardManager, manager);
+
+        // changes that already fell due are applied here and cannot be cancelled
+        update_reward_index(&e);
+        cancel_rate_change(&e, effective_at);
+        cancel_reward_rate_event(&e, effective_at);
+    }
+
+    pub fn scheduled_reward_rates(e: Env) -> Vec<RateChange> {
+        let now = accrual_now(&e);
+        let mut scheduled = Vec::new(&e);
+        for change in read_rate_schedule(&e).iter() {
+            if change.effective_at > now {
+                scheduled.push_back(change);
+            }
+        }
+        scheduled
+    }
+
+    pub fn reward_rate_history(e: Env, from: u64, to: u64) -> Vec<RateChange> {
+        read_rate_history(&e, from, to)
+    }
+
+    pub fn add_amm_address(e: Env, manager: Address, addr: Address) {
+        require_role(&e, Role::RewardManager, manager);
 
         e.storage()
             .instance()
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
         add_amm(&e, addr.clone());
+        sync_static_balance(&e, addr.clone());
         add_amm_event(&e, addr.clone());
     }
 
-    pub fn remove_amm_address(e: Env, addr: Address) {
-        let admin = read_administrator(&e);
-        admin.require_auth();
+    pub fn remove_amm_address(e: Env, manager: Address, addr: Address) {
+        require_role(&e, Role::RewardManager, manager);
 
         e.storage()
             .instance()
@@ -186,11 +837,78 @@ impl ExcellarToken {
 
         remove_amm(&e, addr.clone());
         reset_reward(&e, addr.clone());
+        sync_static_balance(&e, addr.clone());
         remove_amm_event(&e, addr.clone());
     }
     pub fn get_reward(e: Env, to: Address) -> i128 {
         read_reward(&e, to.clone())
     }
+
+    /// Stored reward plus everything accrued since the last checkpoint,
+    /// including the unsettled share of pools the address deposited in.
+    /// Does not write storage.
+    pub fn pending_reward(e: Env, addr: Address) -> i128 {
+        let info = read_reward_info(&e, addr);
+        info.amount + info.pending
+    }
+
+    pub fn reward_info(e: Env, addr: Address) -> RewardInfo {
+        read_reward_info(&e, addr)
+    }
+
+    pub fn grant_role(e: Env, caller: Address, role: Role, addr: Address) {
+        require_role(&e, read_role_admin(&e, role), caller.clone());
+
+        write_role(&e, role, addr.clone());
+        role_granted_event(&e, role, addr, caller);
+    }
+
+    pub fn revoke_role(e: Env, caller: Address, role: Role, addr: Address) {
+        require_role(&e, read_role_admin(&e, role), caller.clone());
+
+        remove_role(&e, role, addr.clone());
+        role_revoked_event(&e, role, addr, caller);
+    }
+
+    pub fn renounce_role(e: Env, addr: Address, role: Role) {
+        addr.require_auth();
+
+        remove_role(&e, role, addr.clone());
+        role_revoked_event(&e, role, addr.clone(), addr);
+    }
+
+    pub fn set_role_admin(e: Env, caller: Address, role: Role, admin_role: Role) {
+        require_role(&e, Role::Admin, caller.clone());
+
+        write_role_admin(&e, role, admin_role);
+        role_admin_changed_event(&e, role, admin_role, caller);
+    }
+
+    pub fn has_role(e: Env, role: Role, addr: Address) -> bool {
+        has_role(&e, role, addr)
+    }
+
+    pub fn role_admin(e: Env, role: Role) -> Role {
+        read_role_admin(&e, role)
+    }
+
+    pub fn pause(e: Env, guardian: Address, scope: PauseScope) {
+        require_role(&e, Role::Guardian, guardian.clone());
+
+        write_paused(&e, scope, true);
+        pause_event(&e, scope, guardian);
+    }
+
+    pub fn unpause(e: Env, guardian: Address, scope: PauseScope) {
+        require_role(&e, Role::Guardian, guardian.clone());
+
+        write_paused(&e, scope, false);
+        unpause_event(&e, scope, guardian);
+    }
+
+    pub fn is_paused(e: Env, scope: PauseScope) -> bool {
+        is_paused(&e, scope)
+    }
 }
 
 #[contractimpl]
@@ -203,7 +921,13 @@ impl token::Interface for ExcellarToken {
     }
 
     fn approve(e: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
-        pre_transfer_checks(&e, from.clone(), spender.clone(), amount);
+        pre_transfer_checks(
+            &e,
+            PauseScope::Approve,
+            from.clone(),
+            spender.clone(),
+            amount,
+        );
 
         e.storage()
             .instance()
@@ -227,7 +951,8 @@ impl token::Interface for ExcellarToken {
             .instance()
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
-        pre_transfer_checks(&e, from.clone(), to.clone(), amount);
+        pre_transfer_checks(&e, PauseScope::Transfer, from.clone(), to.clone(), amount);
+        check_transfer_limits(&e, from.clone(), to.clone(), amount);
 
         checkpoint_reward(&e, from.clone());
         checkpoint_reward(&e, to.clone());
@@ -244,7 +969,15 @@ impl token::Interface for ExcellarToken {
     }
 
     fn transfer_from(e: Env, spender: Address, from: Address, to: Address, amount: i128) {
-        pre_transfer_checks(&e, spender.clone(), to.clone(), amount);
+        pre_transfer_checks(
+            &e,
+            PauseScope::Transfer,
+            spender.clone(),
+            to.clone(),
+            amount,
+        );
+        check_not_blacklisted(&e, from.clone());
+        check_transfer_limits(&e, from.clone(), to.clone(), amount);
 
         e.storage()
             .instance()
@@ -266,8 +999,9 @@ impl token::Interface for ExcellarToken {
     }
 
     fn burn(e: Env, from: Address, amount: i128) {
-        pre_mint_burn_checks(&e, from.clone(), amount);
+        pre_mint_burn_checks(&e, PauseScope::Burn, from.clone(), amount);
         from.require_auth();
+        check_outflow_limit(&e, from.clone(), amount);
 
         e.storage()
             .instance()
@@ -278,8 +1012,32 @@ impl token::Interface for ExcellarToken {
         TokenUtils::new(&e).events().burn(from, amount);
     }
 
-    fn burn_from(_env: Env, _spender: Address, _from: Address, _amount: i128) {
-        panic!("not implemented")
+    fn burn_from(e: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+        check_not_paused(&e, PauseScope::Burn);
+        check_non_negative_amount(&e, amount);
+        // pools hold deposits for others and are never KYC'd themselves
+        let from_amm = is_amm(&e, from.clone());
+        if !from_amm {
+            check_kyc_passed(&e, from.clone());
+        }
+        check_not_blacklisted(&e, from.clone());
+        check_not_blacklisted(&e, spender.clone());
+        check_outflow_limit(&e, from.clone(), amount);
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        checkpoint_reward(&e, from.clone());
+
+        if from_amm {
+            reduce_amm_depositors_pro_rata(&e, from.clone(), amount);
+        }
+
+        spend_allowance(&e, from.clone(), spender, amount);
+        spend_balance(&e, from.clone(), amount);
+        TokenUtils::new(&e).events().burn(from, amount);
     }
 
     fn decimals(e: Env) -> u32 {
@@ -295,20 +1053,53 @@ impl token::Interface for ExcellarToken {
     }
 }
 
-pub fn pre_mint_burn_checks(e: &Env, to: Address, amount: i128) {
-    check_non_negative_amount(amount);
+pub fn pre_mint_burn_checks(e: &Env, scope: PauseScope, to: Address, amount: i128) {
+    check_not_paused(e, scope);
+    check_non_negative_amount(e, amount);
     check_kyc_passed(e, to.clone());
+    check_not_blacklisted(e, to);
 }
 
-pub fn pre_transfer_checks(e: &Env, spender: Address, to: Address, amount: i128) {
+pub fn pre_transfer_checks(
+    e: &Env,
+    scope: PauseScope,
+    spender: Address,
+    to: Address,
+    amount: i128,
+) {
     spender.require_auth();
+    check_not_paused(e, scope);
 
-    check_non_negative_amount(amount);
+    check_non_negative_amount(e, amount);
+    check_not_blacklisted(e, spender);
     check_not_blacklisted(e, to);
 }
 
-pub fn check_non_negative_amount(amount: i128) {
+// pays out one address of a batch, addresses that cannot claim or whose
+// reward cannot be funded are left as they are
+fn distribute_reward(e: &Env, manager: Address, to: Address) {
+    // settles every address, which also brings a rebasing balance in line
+    // with its eligibility
+    checkpoint_reward(e, to.clone());
+    if !is_kyc_passed(e, to.clone())
+        || is_blacklisted(e, to.clone())
+        || !is_claim_allowed(e, to.clone())
+        || is_amm(e, to.clone())
+    {
+        return;
+    }
+    let Some(reward) = affordable_reward(e, read_reward(e, to.clone())) else {
+        return;
+    };
+    if reward < 1 || !can_receive_reward(e, to.clone(), reward) {
+        return;
+    }
+    let paid = pay_reward(e, manager, to.clone(), reward);
+    deduct_reward(e, to, paid);
+}
+
+pub fn check_non_negative_amount(e: &Env, amount: i128) {
     if amount < 0 {
-        panic!("negative amount is not allowed: {}", amount)
+        panic_with_error!(e, ExcellarError::NegativeAmount)
     }
 }
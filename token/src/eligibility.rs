@@ -0,0 +1,158 @@
+use soroban_sdk::{Address, Env};
+
+use crate::storage_types::{
+    DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT,
+    INSTANCE_LIFETIME_THRESHOLD,
+};
+
+fn read_flag(e: &Env, key: DataKey) -> bool {
+    if e.storage().persistent().has(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        true
+    } else {
+        false
+    }
+}
+
+fn write_flag(e: &Env, key: DataKey, set: bool) {
+    if set {
+        e.storage().persistent().set(&key, &true);
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    } else {
+        e.storage().persistent().remove(&key);
+    }
+}
+
+/// Excluded by a reward manager, e.g. exchanges and operational accounts.
+pub fn is_reward_excluded(e: &Env, addr: Address) -> bool {
+    read_flag(e, DataKey::RewardExcluded(addr))
+}
+
+pub fn write_reward_excluded(e: &Env, addr: Address, excluded: bool) {
+    write_flag(e, DataKey::RewardExcluded(addr), excluded);
+}
+
+/// Opted out by the holder itself.
+pub fn is_reward_opted_out(e: &Env, addr: Address) -> bool {
+    read_flag(e, DataKey::RewardOptOut(addr))
+}
+
+pub fn write_reward_opt_out(e: &Env, addr: Address, opted_out: bool) {
+    write_flag(e, DataKey::RewardOptOut(addr), opted_out);
+}
+
+pub fn is_reward_eligible(e: &Env, addr: Address) -> bool {
+    !is_reward_excluded(e, addr.clone()) && !is_reward_opted_out(e, addr)
+}
+
+/// Where the yield ineligible addresses would have earned goes, if anywhere.
+pub fn read_forgone_reward_treasury(e: &Env) -> Option<Address> {
+    let key = DataKey::ForgoneRewardTreasury;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().get(&key)
+}
+
+pub fn write_forgone_reward_treasury(e: &Env, treasury: Option<Address>) {
+    let key = DataKey::ForgoneRewardTreasury;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    match treasury {
+        Some(treasury) => e.storage().instance().set(&key, &treasury),
+        None => e.storage().instance().remove(&key),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use soroban_sdk::{testutils::Address as _, Address, Env};
+
+    use crate::error::ExcellarError;
+    use crate::storage_types::KycTier;
+    use crate::test::set_sequence_number;
+
+    #[test]
+    fn test_excluded_address_does_not_accrue() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let exchange = Address::generate(&e);
+        let treasury = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        let blocks_per_reward: u32 = 28_800;
+        set_sequence_number(&e, 0);
+        token.set_reward_tick(&admin, &blocks_per_reward);
+        token.set_reward_rate(&admin, &10_000_000);
+        token.pass_kyc(&admin, &exchange, &KycTier::Institutional);
+        token.pass_kyc(&admin, &treasury, &KycTier::Institutional);
+        token.mint(&admin, &exchange, &1000);
+
+        // what was earned before the exclusion stays claimable
+        set_sequence_number(&e, blocks_per_reward);
+        token.exclude_from_rewards(&admin, &exchange);
+        assert!(token.reward_excluded(&exchange));
+        assert!(!token.reward_eligible(&exchange));
+        assert_eq!(token.get_reward(&exchange), 100);
+
+        set_sequence_number(&e, blocks_per_reward * 2);
+        assert_eq!(token.pending_reward(&exchange), 100);
+        token.transfer(&exchange, &exchange, &0);
+        assert_eq!(token.get_reward(&exchange), 100);
+
+        // from here the forgone yield goes to the treasury
+        assert_eq!(
+            token.try_set_forgone_reward_treasury(&exchange, &Some(treasury.clone())),
+            Err(Ok(ExcellarError::Unauthorized.into()))
+        );
+        token.set_forgone_reward_treasury(&admin, &Some(treasury.clone()));
+        assert_eq!(token.forgone_reward_treasury(), Some(treasury.clone()));
+        set_sequence_number(&e, blocks_per_reward * 3);
+        token.include_in_rewards(&admin, &exchange);
+        assert!(token.reward_eligible(&exchange));
+        assert_eq!(token.get_reward(&exchange), 100);
+        assert_eq!(token.get_reward(&treasury), 100);
+        token.claim_reward(&treasury);
+        assert_eq!(token.balance(&treasury), 100);
+    }
+
+    #[test]
+    fn test_self_service_opt_out() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        let blocks_per_reward: u32 = 28_800;
+        set_sequence_number(&e, 0);
+        token.set_reward_tick(&admin, &blocks_per_reward);
+        token.set_reward_rate(&admin, &10_000_000);
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+        token.mint(&admin, &user, &1000);
+        token.set_reward_opt_out(&user, &true);
+        assert!(token.reward_opted_out(&user));
+
+        // an exclusion lifted by the manager does not undo the opt-out
+        token.exclude_from_rewards(&admin, &user);
+        token.include_in_rewards(&admin, &user);
+        assert!(!token.reward_eligible(&user));
+
+        set_sequence_number(&e, blocks_per_reward);
+        token.claim_reward(&user);
+        assert_eq!(token.balance(&user), 1000);
+
+        token.set_reward_opt_out(&user, &false);
+        set_sequence_number(&e, blocks_per_reward * 2);
+        token.claim_reward(&user);
+        assert_eq!(token.balance(&user), 1100);
+    }
+}
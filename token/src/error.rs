@@ -0,0 +1,46 @@
+use soroban_sdk::contracterror;
+
+// Codes are part of the public contract interface, never renumber existing variants.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum ExcellarError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    DecimalTooLarge = 3,
+    NegativeAmount = 4,
+    KycNotPassed = 5,
+    Blacklisted = 6,
+    AmmNotAllowed = 7,
+    InsufficientBalance = 8,
+    InsufficientAllowance = 9,
+    InsufficientTotalSupply = 10,
+    InvalidExpirationLedger = 11,
+    AmmPoolEmpty = 12,
+    Unauthorized = 13,
+    InvalidRole = 14,
+    NoPendingAdmin = 15,
+    AdminProposalExpired = 16,
+    Paused = 17,
+    BalanceFrozen = 18,
+    InsufficientFrozenBalance = 19,
+    KycExpired = 20,
+    TierBalanceLimit = 21,
+    TierTransferLimit = 22,
+    TierDailyOutflowLimit = 23,
+    TierAmmNotAllowed = 24,
+    TierClaimNotAllowed = 25,
+    InvalidEffectiveLedger = 26,
+    RateChangeNotScheduled = 27,
+    AlreadyRebasing = 28,
+    InsufficientRewardFunds = 29,
+    BatchTooLarge = 30,
+    InvalidApy = 31,
+    KycManagedByRegistry = 32,
+    InvalidRewardTick = 33,
+    RateScheduleFull = 34,
+    ArithmeticOverflow = 35,
+    RebasingRequiresMinting = 36,
+    HolderNotFound = 37,
+    ForgoneRewardTreasuryNotSet = 38,
+}
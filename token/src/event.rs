@@ -1,8 +1,13 @@
 use soroban_sdk::{Address, Env, Symbol};
 
-pub(crate) fn pass_kyc_event(e: &Env, user: Address) {
+use crate::storage_types::{
+    BlacklistRewardPolicy, IdentityRegistryConfig, KycRevocationRewardPolicy, KycTier, PauseScope,
+    RateChange, RewardAsset, RewardFunding, Role, ShortfallPolicy,
+};
+
+pub(crate) fn pass_kyc_event(e: &Env, user: Address, tier: KycTier) {
     let topics = (Symbol::new(e, "pass_kyc"), user);
-    e.events().publish(topics, true);
+    e.events().publish(topics, tier);
 }
 
 pub(crate) fn fail_kyc_event(e: &Env, user: Address) {
@@ -10,6 +15,26 @@ pub(crate) fn fail_kyc_event(e: &Env, user: Address) {
     e.events().publish(topics, true);
 }
 
+pub(crate) fn kyc_expired_event(e: &Env, user: Address, expires_at: u32) {
+    let topics = (Symbol::new(e, "kyc_expired"), user);
+    e.events().publish(topics, expires_at);
+}
+
+pub(crate) fn kyc_validity_event(e: &Env, ledgers: u32) {
+    let topics = (Symbol::new(e, "kyc_validity"),);
+    e.events().publish(topics, ledgers);
+}
+
+pub(crate) fn identity_registry_event(e: &Env, config: Option<IdentityRegistryConfig>) {
+    let topics = (Symbol::new(e, "identity_registry"),);
+    e.events().publish(topics, config);
+}
+
+pub(crate) fn tier_policy_event(e: &Env, tier: KycTier) {
+    let topics = (Symbol::new(e, "tier_policy"), tier);
+    e.events().publish(topics, true);
+}
+
 pub(crate) fn whitelist_event(e: &Env, user: Address) {
     let topics = (Symbol::new(e, "whitelist"), user);
     e.events().publish(topics, true);
@@ -20,6 +45,101 @@ pub(crate) fn blacklist_event(e: &Env, user: Address) {
     e.events().publish(topics, true);
 }
 
+pub(crate) fn blacklist_reward_policy_event(e: &Env, policy: BlacklistRewardPolicy) {
+    let topics = (Symbol::new(e, "blacklist_reward_policy"),);
+    e.events().publish(topics, policy);
+}
+
+pub(crate) fn kyc_revocation_reward_policy_event(e: &Env, policy: KycRevocationRewardPolicy) {
+    let topics = (Symbol::new(e, "kyc_revocation_reward_policy"),);
+    e.events().publish(topics, policy);
+}
+
+pub(crate) fn freeze_reward_event(e: &Env, user: Address, amount: i128) {
+    let topics = (Symbol::new(e, "freeze_reward"), user);
+    e.events().publish(topics, amount);
+}
+
+pub(crate) fn forfeit_reward_event(e: &Env, user: Address, amount: i128) {
+    let topics = (Symbol::new(e, "forfeit_reward"), user);
+    e.events().publish(topics, amount);
+}
+
+pub(crate) fn freeze_event(e: &Env, user: Address, amount: i128) {
+    let topics = (Symbol::new(e, "freeze"), user);
+    e.events().publish(topics, amount);
+}
+
+pub(crate) fn unfreeze_event(e: &Env, user: Address, amount: i128) {
+    let topics = (Symbol::new(e, "unfreeze"), user);
+    e.events().publish(topics, amount);
+}
+
+pub(crate) fn reward_exclusion_event(e: &Env, user: Address, excluded: bool) {
+    let topics = (Symbol::new(e, "reward_exclusion"), user);
+    e.events().publish(topics, excluded);
+}
+
+pub(crate) fn reward_opt_out_event(e: &Env, user: Address, opted_out: bool) {
+    let topics = (Symbol::new(e, "reward_opt_out"), user);
+    e.events().publish(topics, opted_out);
+}
+
+pub(crate) fn forgone_reward_treasury_event(e: &Env, manager: Address, treasury: Option<Address>) {
+    let topics = (Symbol::new(e, "forgone_reward_treasury"), manager);
+    e.events().publish(topics, treasury);
+}
+
+pub(crate) fn reward_funding_event(e: &Env, manager: Address, funding: RewardFunding) {
+    let topics = (Symbol::new(e, "reward_funding"), manager);
+    e.events().publish(topics, funding);
+}
+
+pub(crate) fn reward_asset_event(e: &Env, manager: Address, asset: Option<RewardAsset>) {
+    let topics = (Symbol::new(e, "reward_asset"), manager);
+    e.events().publish(topics, asset);
+}
+
+pub(crate) fn shortfall_policy_event(e: &Env, manager: Address, policy: ShortfallPolicy) {
+    let topics = (Symbol::new(e, "shortfall_policy"), manager);
+    e.events().publish(topics, policy);
+}
+
+pub(crate) fn fund_reward_reserve_event(e: &Env, from: Address, amount: i128) {
+    let topics = (Symbol::new(e, "fund_reward_reserve"), from);
+    e.events().publish(topics, amount);
+}
+
+pub(crate) fn withdraw_reward_reserve_event(e: &Env, to: Address, amount: i128) {
+    let topics = (Symbol::new(e, "withdraw_reward_reserve"), to);
+    e.events().publish(topics, amount);
+}
+
+pub(crate) fn rebasing_event(e: &Env, total_shares: i128) {
+    let topics = (Symbol::new(e, "rebasing"),);
+    e.events().publish(topics, total_shares);
+}
+
+pub(crate) fn auto_compound_event(e: &Env, user: Address, enabled: bool) {
+    let topics = (Symbol::new(e, "auto_compound"), user);
+    e.events().publish(topics, enabled);
+}
+
+pub(crate) fn reward_claimer_event(e: &Env, owner: Address, claimer: Option<Address>) {
+    let topics = (Symbol::new(e, "reward_claimer"), owner);
+    e.events().publish(topics, claimer);
+}
+
+pub(crate) fn default_auto_compound_event(e: &Env, enabled: bool) {
+    let topics = (Symbol::new(e, "default_auto_compound"),);
+    e.events().publish(topics, enabled);
+}
+
+pub(crate) fn frozen_reward_accrual_event(e: &Env, enabled: bool) {
+    let topics = (Symbol::new(e, "frozen_reward_accrual"),);
+    e.events().publish(topics, enabled);
+}
+
 pub(crate) fn add_amm_event(e: &Env, address: Address) {
     let topics = (Symbol::new(e, "add_amm"), address);
     e.events().publish(topics, true);
@@ -29,3 +149,70 @@ pub(crate) fn remove_amm_event(e: &Env, address: Address) {
     let topics = (Symbol::new(e, "remove_amm"), address);
     e.events().publish(topics, true);
 }
+
+pub(crate) fn propose_admin_event(
+    e: &Env,
+    admin: Address,
+    new_admin: Address,
+    expiration_ledger: u32,
+) {
+    let topics = (Symbol::new(e, "propose_admin"), admin, new_admin);
+    e.events().publish(topics, expiration_ledger);
+}
+
+pub(crate) fn cancel_admin_proposal_event(e: &Env, admin: Address, new_admin: Address) {
+    let topics = (Symbol::new(e, "cancel_admin_proposal"), admin, new_admin);
+    e.events().publish(topics, true);
+}
+
+pub(crate) fn clawback_event(e: &Env, operator: Address, from: Address, amount: i128, reason: u32) {
+    let topics = (Symbol::new(e, "enforce_clawback"), operator, from);
+    e.events().publish(topics, (amount, reason));
+}
+
+pub(crate) fn force_transfer_event(
+    e: &Env,
+    operator: Address,
+    from: Address,
+    to: Address,
+    amount: i128,
+    reason: u32,
+) {
+    let topics = (Symbol::new(e, "enforce_transfer"), operator, from);
+    e.events().publish(topics, (to, amount, reason));
+}
+
+pub(crate) fn schedule_reward_rate_event(e: &Env, change: RateChange) {
+    let topics = (Symbol::new(e, "schedule_reward_rate"), change.effective_at);
+    e.events().publish(topics, (change.rate, change.tick));
+}
+
+pub(crate) fn cancel_reward_rate_event(e: &Env, effective_at: u64) {
+    let topics = (Symbol::new(e, "cancel_reward_rate"), effective_at);
+    e.events().publish(topics, true);
+}
+
+pub(crate) fn role_granted_event(e: &Env, role: Role, addr: Address, caller: Address) {
+    let topics = (Symbol::new(e, "role_granted"), role, addr);
+    e.events().publish(topics, caller);
+}
+
+pub(crate) fn role_revoked_event(e: &Env, role: Role, addr: Address, caller: Address) {
+    let topics = (Symbol::new(e, "role_revoked"), role, addr);
+    e.events().publish(topics, caller);
+}
+
+pub(crate) fn role_admin_changed_event(e: &Env, role: Role, admin_role: Role, caller: Address) {
+    let topics = (Symbol::new(e, "role_admin_changed"), role);
+    e.events().publish(topics, (admin_role, caller));
+}
+
+pub(crate) fn pause_event(e: &Env, scope: PauseScope, guardian: Address) {
+    let topics = (Symbol::new(e, "pause"), scope);
+    e.events().publish(topics, guardian);
+}
+
+pub(crate) fn unpause_event(e: &Env, scope: PauseScope, guardian: Address) {
+    let topics = (Symbol::new(e, "unpause"), scope);
+    e.events().publish(topics, guardian);
+}
@@ -0,0 +1,295 @@
+//! Fixed-point helpers for reward accrual. Products are carried in 256 bits
+//! so intermediates never overflow, only a result outside `i128` does.
+
+use soroban_sdk::{panic_with_error, Env};
+
+use crate::error::ExcellarError;
+
+/// How a quotient that is not exact gets rounded.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Rounding {
+    /// Towards zero.
+    Down,
+    /// Away from zero.
+    Up,
+    /// To the nearest value, halves away from zero.
+    HalfUp,
+}
+
+const LOW_MASK: u128 = u64::MAX as u128;
+
+// full 256-bit product as (high, low) halves
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    let (a_high, a_low) = (a >> 64, a & LOW_MASK);
+    let (b_high, b_low) = (b >> 64, b & LOW_MASK);
+    let low_low = a_low * b_low;
+    let high_low = a_high * b_low;
+    let low_high = a_low * b_high;
+    let high_high = a_high * b_high;
+
+    let cross = (low_low >> 64) + (high_low & LOW_MASK) + (low_high & LOW_MASK);
+    let high = high_high + (high_low >> 64) + (low_high >> 64) + (cross >> 64);
+    let low = (cross << 64) | (low_low & LOW_MASK);
+    (high, low)
+}
+
+// quotient and remainder of a 256-bit value by `divisor`, `None` when the
+// quotient does not fit in 128 bits
+fn div_wide(high: u128, low: u128, divisor: u128) -> Option<(u128, u128)> {
+    if high >= divisor {
+        return None;
+    }
+    if high == 0 {
+        return Some((low / divisor, low % divisor));
+    }
+    let (mut quotient, mut remainder) = (0u128, high);
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= divisor {
+            remainder = remainder.wrapping_sub(divisor);
+            quotient |= 1;
+        }
+    }
+    Some((quotient, remainder))
+}
+
+/// `a * b / denominator` rounded as requested, `None` when the denominator
+/// is zero or the result does not fit in `i128`.
+pub fn checked_mul_div(a: i128, b: i128, denominator: i128, rounding: Rounding) -> Option<i128> {
+    if denominator == 0 {
+        return None;
+    }
+    let negative = (a < 0) ^ (b < 0) ^ (denominator < 0);
+    let divisor = denominator.unsigned_abs();
+    let (high, low) = mul_wide(a.unsigned_abs(), b.unsigned_abs());
+    let (mut quotient, remainder) = div_wide(high, low, divisor)?;
+
+    let round_away = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => remainder > 0,
+        Rounding::HalfUp => remainder >= divisor - remainder,
+    };
+    if round_away {
+        quotient = quotient.checked_add(1)?;
+    }
+
+    if negative {
+        0i128.checked_sub_unsigned(quotient)
+    } else {
+        i128::try_from(quotient).ok()
+    }
+}
+
+/// e^x for a non-negative `x` scaled by `one`, rounded down, `None` when the
+/// result does not fit in `i128`.
+pub fn checked_exp(x: i128, one: i128) -> Option<i128> {
+    // e^x = (e^(x / 2^k))^(2^k), with the series summed below one
+    let (mut reduced, mut squarings) = (x, 0);
+    while reduced > one {
+        reduced /= 2;
+        squarings += 1;
+    }
+    let (mut term, mut k, mut result) = (one, 0, 0i128);
+    while term != 0 {
+        result = result.checked_add(term)?;
+        k += 1;
+        term = checked_mul_div(term, reduced, one * k, Rounding::Down)?;
+    }
+    for _ in 0..squarings {
+        result = checked_mul_div(result, result, one, Rounding::Down)?;
+    }
+    Some(result)
+}
+
+/// `a * b / denominator` rounded as requested, failing with
+/// `ArithmeticOverflow` when the denominator is zero or the result does not
+/// fit in `i128`.
+pub fn mul_div(e: &Env, a: i128, b: i128, denominator: i128, rounding: Rounding) -> i128 {
+    match checked_mul_div(a, b, denominator, rounding) {
+        Some(result) => result,
+        None => panic_with_error!(e, ExcellarError::ArithmeticOverflow),
+    }
+}
+
+/// `a * b / denominator` rounded down and the remainder of that division,
+/// for non-negative operands, failing like `mul_div`.
+pub fn mul_div_rem(e: &Env, a: i128, b: i128, denominator: i128) -> (i128, i128) {
+    let quotient = mul_div(e, a, b, denominator, Rounding::Down);
+    // the remainder is below the denominator, so the low halves of the two
+    // products differ by exactly that much
+    let (_, product) = mul_wide(a.unsigned_abs(), b.unsigned_abs());
+    let (_, divided) = mul_wide(quotient.unsigned_abs(), denominator.unsigned_abs());
+    (quotient, product.wrapping_sub(divided) as i128)
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use soroban_sdk::Env;
+
+    use crate::fixed_point::{
+        checked_exp, checked_mul_div, mul_div, mul_div_rem, mul_wide, Rounding,
+    };
+    use crate::reward::{_calculate_reward, apply_index, index_delta};
+
+    // xorshift, enough to spread the sampled inputs
+    fn next(seed: &mut u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed
+    }
+
+    // a value of random magnitude, from a few bits up to the full i128 range
+    fn sample(seed: &mut u64) -> i128 {
+        let wide = ((next(seed) as u128) << 64) | next(seed) as u128;
+        let bits = next(seed) % 128;
+        let value = (wide >> (127 - bits)) as i128;
+        if next(seed) & 3 == 0 {
+            -value
+        } else {
+            value
+        }
+    }
+
+    fn less_or_equal(a: (u128, u128), b: (u128, u128)) -> bool {
+        a.0 < b.0 || (a.0 == b.0 && a.1 <= b.1)
+    }
+
+    #[test]
+    fn test_mul_div_brackets_the_exact_quotient() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15;
+        for _ in 0..20_000 {
+            let (a, b, d) = (sample(&mut seed), sample(&mut seed), sample(&mut seed));
+            let Some(q) = checked_mul_div(a, b, d, Rounding::Down) else {
+                continue;
+            };
+            // |q| * |d| <= |a * b| < (|q| + 1) * |d|
+            let product = mul_wide(a.unsigned_abs(), b.unsigned_abs());
+            let lower = mul_wide(q.unsigned_abs(), d.unsigned_abs());
+            let upper = mul_wide(q.unsigned_abs() + 1, d.unsigned_abs());
+            assert!(less_or_equal(lower, product), "{a} * {b} / {d} = {q}");
+            assert!(!less_or_equal(upper, product), "{a} * {b} / {d} = {q}");
+
+            if let Some(expected) = a.checked_mul(b) {
+                assert_eq!(q, expected / d);
+            }
+            let up = checked_mul_div(a, b, d, Rounding::Up);
+            let half = checked_mul_div(a, b, d, Rounding::HalfUp);
+            let exact = lower == product;
+            let away = if q < 0 || (q == 0 && (a < 0) ^ (b < 0) ^ (d < 0)) {
+                q.checked_sub(1)
+            } else {
+                q.checked_add(1)
+            };
+            assert_eq!(up, if exact { Some(q) } else { away });
+            assert!(half == Some(q) || half == away);
+        }
+    }
+
+    #[test]
+    fn test_mul_div_extremes() {
+        let e = Env::default();
+        assert_eq!(
+            mul_div(&e, i128::MAX, i128::MAX, i128::MAX, Rounding::Down),
+            i128::MAX
+        );
+        assert_eq!(
+            mul_div(&e, i128::MIN, i128::MAX, i128::MAX, Rounding::Down),
+            i128::MIN
+        );
+        assert_eq!(checked_mul_div(i128::MAX, 2, 1, Rounding::Down), None);
+        assert_eq!(checked_mul_div(1, 1, 0, Rounding::Down), None);
+        assert_eq!(mul_div(&e, 7, 1, 2, Rounding::Down), 3);
+        assert_eq!(mul_div(&e, 7, 1, 2, Rounding::Up), 4);
+        assert_eq!(mul_div(&e, 7, 1, 2, Rounding::HalfUp), 4);
+        assert_eq!(mul_div(&e, -7, 1, 2, Rounding::Down), -3);
+        assert_eq!(mul_div(&e, -7, 1, 2, Rounding::Up), -4);
+        assert_eq!(mul_div(&e, -7, 1, 2, Rounding::HalfUp), -4);
+        assert_eq!(mul_div(&e, 5, 1, 3, Rounding::HalfUp), 2);
+        assert_eq!(mul_div(&e, 4, 1, 3, Rounding::HalfUp), 1);
+        assert_eq!(mul_div_rem(&e, 7, 1, 2), (3, 1));
+        assert_eq!(
+            mul_div_rem(&e, i128::MAX, 3, 4),
+            (127_605_887_595_351_923_798_765_477_786_913_079_295, 1)
+        );
+    }
+
+    #[test]
+    fn test_exp() {
+        const ONE: i128 = 1_000_000_000_000_000_000;
+        assert_eq!(checked_exp(0, ONE), Some(ONE));
+        // e, e^0.1 and e^10 to 15 significant digits
+        for (x, expected) in [
+            (ONE, 2_718_281_828_459_045_235),
+            (ONE / 10, 1_105_170_918_075_647_624),
+            (10 * ONE, 22_026_465_794_806_716_516_957),
+        ] {
+            let result = checked_exp(x, ONE).unwrap();
+            assert!(
+                (result - expected).abs() <= expected / 1_000_000_000_000_000,
+                "{x}"
+            );
+        }
+        // e^x * e^y is e^(x + y) however the exponent is split
+        let whole = checked_exp(3 * ONE / 2, ONE).unwrap();
+        let split = mul_div(
+            &Env::default(),
+            checked_exp(ONE / 2, ONE).unwrap(),
+            checked_exp(ONE, ONE).unwrap(),
+            ONE,
+            Rounding::Down,
+        );
+        assert!((whole - split).abs() < 100);
+        assert_eq!(checked_exp(100 * ONE, ONE), None);
+    }
+
+    #[test]
+    fn test_index_reward_over_extreme_balances_and_gaps() {
+        const ONE: i128 = 1_000_000_000_000_000_000;
+        let e = Env::default();
+        let mut seed = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..2_000 {
+            // up to a century of ledgers between two index updates
+            let elapsed = next(&mut seed) % (100 * 365 * 17_280);
+            let tick = next(&mut seed) as u32 % 1_000_000 + 1;
+            // up to 100% per tick
+            let rate = next(&mut seed) as u32 % 100_000_000 + 1;
+            let periods = elapsed as i128 / tick as i128 + 1;
+            let delta = index_delta(&e, elapsed, rate, tick);
+            assert!(delta >= 0);
+            assert!(delta <= periods * ONE);
+
+            // any balance whose reward still fits in i128
+            let balance = sample(&mut seed).abs() / periods;
+            let reward = apply_index(&e, balance, delta);
+            assert!(reward >= 0);
+            assert!(reward <= balance * periods);
+        }
+        // ten years at 5% a year on half the largest balance
+        let year = 365 * 17_280;
+        let delta = index_delta(&e, 10 * year, 5_000_000, year as u32);
+        assert_eq!(delta, ONE / 2);
+        let balance = i128::MAX / 2;
+        assert_eq!(apply_index(&e, balance, delta), balance / 2 + 1);
+    }
+
+    #[test]
+    fn test_legacy_reward_over_large_balance() {
+        // a year of ledgers on a balance that overflowed the old arithmetic
+        let balance = 10i128.pow(30);
+        assert_eq!(
+            _calculate_reward(
+                &Env::default(),
+                365 * 17_280,
+                balance,
+                5_000_000,
+                365 * 17_280
+            ),
+            balance / 20
+        );
+    }
+}
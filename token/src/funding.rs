@@ -0,0 +1,215 @@
+use soroban_sdk::{panic_with_error, token, Address, Env};
+use soroban_token_sdk::TokenUtils;
+
+use crate::admin::is_blacklisted;
+use crate::allowance::{read_allowance, spend_allowance};
+use crate::balance::{read_balance, read_frozen_balance, receive_balance, spend_balance};
+use crate::error::ExcellarError;
+use crate::metadata::read_decimal;
+use crate::reward::checkpoint_reward;
+use crate::storage_types::{
+    DataKey, RewardAsset, RewardFunding, ShortfallPolicy, INSTANCE_BUMP_AMOUNT,
+    INSTANCE_LIFETIME_THRESHOLD,
+};
+use crate::tier::{check_balance_limit, is_within_balance_limit};
+
+pub fn read_reward_funding(e: &Env) -> RewardFunding {
+    let key = DataKey::RewardFunding;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage()
+        .instance()
+        .get(&key)
+        .unwrap_or(RewardFunding::Mint)
+}
+
+pub fn write_reward_funding(e: &Env, funding: RewardFunding) {
+    let key = DataKey::RewardFunding;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().set(&key, &funding);
+}
+
+pub fn read_shortfall_policy(e: &Env) -> ShortfallPolicy {
+    let key = DataKey::ShortfallPolicy;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage()
+        .instance()
+        .get(&key)
+        .unwrap_or(ShortfallPolicy::Fail)
+}
+
+pub fn write_shortfall_policy(e: &Env, policy: ShortfallPolicy) {
+    let key = DataKey::ShortfallPolicy;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().set(&key, &policy);
+}
+
+pub fn read_reward_asset(e: &Env) -> Option<RewardAsset> {
+    let key = DataKey::RewardAsset;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().get(&key)
+}
+
+pub fn write_reward_asset(e: &Env, asset: Option<RewardAsset>) {
+    let key = DataKey::RewardAsset;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    match asset {
+        Some(asset) => e.storage().instance().set(&key, &asset),
+        None => e.storage().instance().remove(&key),
+    }
+}
+
+// rewards accrue in units of this token, the asset may use other decimals
+fn to_asset_amount(e: &Env, client: &token::Client, amount: i128) -> i128 {
+    let (decimal, asset_decimal) = (read_decimal(e), client.decimals());
+    let converted = if asset_decimal >= decimal {
+        10i128
+            .checked_pow(asset_decimal - decimal)
+            .and_then(|scale| amount.checked_mul(scale))
+    } else {
+        Some(
+            10i128
+                .checked_pow(decimal - asset_decimal)
+                .map_or(0, |scale| amount / scale),
+        )
+    };
+    match converted {
+        Some(converted) => converted,
+        None => panic_with_error!(e, ExcellarError::ArithmeticOverflow),
+    }
+}
+
+// saturates, an amount of the asset beyond what this token can count is
+// still more than any reward
+fn from_asset_amount(e: &Env, client: &token::Client, amount: i128) -> i128 {
+    let (decimal, asset_decimal) = (read_decimal(e), client.decimals());
+    if asset_decimal >= decimal {
+        10i128
+            .checked_pow(asset_decimal - decimal)
+            .map_or(0, |scale| amount / scale)
+    } else {
+        10i128
+            .checked_pow(decimal - asset_decimal)
+            .and_then(|scale| amount.checked_mul(scale))
+            .unwrap_or(i128::MAX)
+    }
+}
+
+fn available_asset_funds(e: &Env, reward_asset: RewardAsset) -> i128 {
+    let client = token::Client::new(e, &reward_asset.asset);
+    let contract = e.current_contract_address();
+    let mut available = client.balance(&reward_asset.vault);
+    if reward_asset.vault != contract {
+        available = available.min(client.allowance(&reward_asset.vault, &contract));
+    }
+    from_asset_amount(e, &client, available)
+}
+
+pub fn read_reward_reserve(e: &Env) -> i128 {
+    read_balance(e, e.current_contract_address())
+}
+
+// the funding source is never paid its own reward through auto-compounding
+pub fn is_reward_funding_source(e: &Env, addr: Address) -> bool {
+    match read_reward_funding(e) {
+        RewardFunding::Mint => false,
+        RewardFunding::Treasury(treasury) => treasury == addr,
+        RewardFunding::Reserve => e.current_contract_address() == addr,
+    }
+}
+
+/// Amount the configured funding source can pay out right now, in units of
+/// this token, unlimited when rewards are minted.
+pub fn available_reward_funds(e: &Env) -> i128 {
+    if let Some(reward_asset) = read_reward_asset(e) {
+        return available_asset_funds(e, reward_asset);
+    }
+    match read_reward_funding(e) {
+        RewardFunding::Mint => i128::MAX,
+        RewardFunding::Treasury(treasury) => {
+            if is_blacklisted(e, treasury.clone()) {
+                return 0;
+            }
+            let spendable =
+                read_balance(e, treasury.clone()) - read_frozen_balance(e, treasury.clone());
+            let allowance = read_allowance(e, treasury, e.current_contract_address()).amount;
+            spendable.min(allowance).max(0)
+        }
+        RewardFunding::Reserve => read_reward_reserve(e),
+    }
+}
+
+/// Part of `reward` that a claim pays, according to the shortfall policy.
+pub fn payable_reward(e: &Env, reward: i128) -> i128 {
+    match affordable_reward(e, reward) {
+        Some(reward) => reward,
+        None => panic_with_error!(e, ExcellarError::InsufficientRewardFunds),
+    }
+}
+
+/// Like `payable_reward`, but `None` when the policy refuses the payout.
+pub fn affordable_reward(e: &Env, reward: i128) -> Option<i128> {
+    let available = available_reward_funds(e);
+    if reward > available && read_shortfall_policy(e) == ShortfallPolicy::Fail {
+        return None;
+    }
+    Some(reward.min(available))
+}
+
+/// Whether `to` can take a payout of `amount` without going over the
+/// balance limit of its tier, which only applies to rewards paid in this token.
+pub fn can_receive_reward(e: &Env, to: Address, amount: i128) -> bool {
+    read_reward_asset(e).is_some() || is_within_balance_limit(e, to, amount)
+}
+
+/// Moves `amount` of reward from the funding source to `to`, `minter` is
+/// reported as the admin of the mint event when rewards are minted. With a
+/// reward asset configured the amount is paid in that asset instead, rounded
+/// down to what it can represent. Returns the part of `amount` that was paid.
+pub fn pay_reward(e: &Env, minter: Address, to: Address, amount: i128) -> i128 {
+    let contract = e.current_contract_address();
+    if let Some(reward_asset) = read_reward_asset(e) {
+        let client = token::Client::new(e, &reward_asset.asset);
+        let asset_amount = to_asset_amount(e, &client, amount);
+        if asset_amount < 1 {
+            return 0;
+        }
+        if reward_asset.vault == contract {
+            client.transfer(&contract, &to, &asset_amount);
+        } else {
+            client.transfer_from(&contract, &reward_asset.vault, &to, &asset_amount);
+        }
+        return from_asset_amount(e, &client, asset_amount);
+    }
+    check_balance_limit(e, to.clone(), amount);
+    match read_reward_funding(e) {
+        RewardFunding::Mint => {
+            receive_balance(e, to.clone(), amount);
+            TokenUtils::new(e).events().mint(minter, to, amount);
+        }
+        RewardFunding::Treasury(treasury) => {
+            checkpoint_reward(e, treasury.clone());
+            spend_allowance(e, treasury.clone(), contract, amount);
+            spend_balance(e, treasury.clone(), amount);
+            receive_balance(e, to.clone(), amount);
+            TokenUtils::new(e).events().transfer(treasury, to, amount);
+        }
+        RewardFunding::Reserve => {
+            spend_balance(e, contract.clone(), amount);
+            receive_balance(e, to.clone(), amount);
+            TokenUtils::new(e).events().transfer(contract, to, amount);
+        }
+    }
+    amount
+}
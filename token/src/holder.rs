@@ -0,0 +1,103 @@
+use soroban_sdk::{panic_with_error, Address, Env};
+
+use crate::balance::{read_balance, read_frozen_balance};
+use crate::error::ExcellarError;
+use crate::reward::read_reward;
+use crate::storage_types::{
+    DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT,
+    INSTANCE_LIFETIME_THRESHOLD,
+};
+
+/// Most addresses a reward batch pays out. Settling and paying one holder
+/// measures about 2.2M cpu instructions and 460KB of memory, a full batch
+/// 56M instructions and 11.5MB, which leaves the default 100M / 40MB budget
+/// room for treasury or reserve funding and for pruning the holder index.
+pub const MAX_BATCH_SIZE: u32 = 25;
+
+/// Number of addresses in the holder index. Every address that receives a
+/// balance or a reward is indexed once, in the order it first did, until a
+/// distribution finds it holding nothing.
+pub fn read_holder_count(e: &Env) -> u32 {
+    let key = DataKey::HolderCount;
+    if let Some(count) = e.storage().persistent().get::<DataKey, u32>(&key) {
+        e.storage().persistent().extend_ttl(
+            &key,
+            INSTANCE_LIFETIME_THRESHOLD,
+            INSTANCE_BUMP_AMOUNT,
+        );
+        count
+    } else {
+        0
+    }
+}
+
+pub fn read_holder(e: &Env, position: u32) -> Address {
+    let key = DataKey::Holder(position);
+    let Some(holder) = e.storage().persistent().get(&key) else {
+        panic_with_error!(e, ExcellarError::HolderNotFound)
+    };
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    holder
+}
+
+pub fn add_holder(e: &Env, addr: Address) {
+    let key = DataKey::HolderIndexed(addr.clone());
+    if e.storage().persistent().has(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        return;
+    }
+    e.storage().persistent().set(&key, &true);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+
+    let count = read_holder_count(e);
+    let holder_key = DataKey::Holder(count);
+    e.storage().persistent().set(&holder_key, &addr);
+    e.storage().persistent().extend_ttl(
+        &holder_key,
+        BALANCE_LIFETIME_THRESHOLD,
+        BALANCE_BUMP_AMOUNT,
+    );
+
+    write_holder_count(e, count + 1);
+}
+
+// nothing left to distribute, the address is indexed again once it receives
+pub fn holds_nothing(e: &Env, addr: Address) -> bool {
+    read_balance(e, addr.clone()) == 0
+        && read_frozen_balance(e, addr.clone()) == 0
+        && read_reward(e, addr) == 0
+}
+
+/// Drops the holder at `position` from the index, the last holder takes its
+/// place.
+pub fn remove_holder(e: &Env, position: u32) {
+    let holder = read_holder(e, position);
+    let last = read_holder_count(e) - 1;
+    if position != last {
+        let moved = read_holder(e, last);
+        let key = DataKey::Holder(position);
+        e.storage().persistent().set(&key, &moved);
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    }
+    e.storage().persistent().remove(&DataKey::Holder(last));
+    e.storage()
+        .persistent()
+        .remove(&DataKey::HolderIndexed(holder));
+    write_holder_count(e, last);
+}
+
+fn write_holder_count(e: &Env, count: u32) {
+    let key = DataKey::HolderCount;
+    e.storage().persistent().set(&key, &count);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
@@ -1,13 +1,33 @@
 #![no_std]
 pub use crate::contract::ExcellarTokenClient;
+pub use crate::error::ExcellarError;
+pub use crate::registry::{IdentityRegistry, IdentityRegistryClient};
+pub use crate::storage_types::{
+    AccrualClock, BlacklistRewardPolicy, IdentityRegistryConfig, KycRecord,
+    KycRevocationRewardPolicy, KycTier, PauseScope, RateChange, RewardAsset, RewardFunding,
+    RewardInfo, Role, ShortfallPolicy, TierPolicy,
+};
 
 mod admin;
 mod allowance;
 mod amm;
+mod apy;
 mod balance;
+mod clock;
 mod contract;
+mod eligibility;
+mod error;
 mod event;
+mod fixed_point;
+mod funding;
+mod holder;
 mod metadata;
+mod pause;
+mod rebase;
+mod registry;
 mod reward;
+mod role;
+mod schedule;
 mod storage_types;
 mod test;
+mod tier;
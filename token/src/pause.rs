@@ -0,0 +1,220 @@
+use soroban_sdk::{panic_with_error, Env};
+
+use crate::error::ExcellarError;
+use crate::storage_types::{
+    DataKey, PauseScope, INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD,
+};
+
+pub fn is_paused(e: &Env, scope: PauseScope) -> bool {
+    let key = DataKey::Paused(scope);
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().get(&key).unwrap_or(false)
+}
+
+pub fn write_paused(e: &Env, scope: PauseScope, paused: bool) {
+    let key = DataKey::Paused(scope);
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    if paused {
+        e.storage().instance().set(&key, &true);
+    } else {
+        e.storage().instance().remove(&key);
+    }
+}
+
+pub fn check_not_paused(e: &Env, scope: PauseScope) {
+    if is_paused(e, scope) {
+        panic_with_error!(e, ExcellarError::Paused)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use soroban_sdk::{
+        testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation},
+        Address, Env, IntoVal, Symbol,
+    };
+
+    use crate::error::ExcellarError;
+    use crate::storage_types::{KycTier, PauseScope, Role};
+    use crate::test::set_sequence_number;
+
+    #[test]
+    fn test_pause() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let guardian = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        token.grant_role(&admin, &Role::Guardian, &guardian);
+
+        token.pause(&guardian, &PauseScope::Transfer);
+        assert_eq!(
+            e.auths(),
+            std::vec![(
+                guardian.clone(),
+                AuthorizedInvocation {
+                    function: AuthorizedFunction::Contract((
+                        token.address.clone(),
+                        Symbol::new(&e, "pause"),
+                        (&guardian, PauseScope::Transfer).into_val(&e),
+                    )),
+                    sub_invocations: std::vec![],
+                }
+            )]
+        );
+        assert!(token.is_paused(&PauseScope::Transfer));
+        assert!(!token.is_paused(&PauseScope::Mint));
+
+        token.unpause(&guardian, &PauseScope::Transfer);
+        assert!(!token.is_paused(&PauseScope::Transfer));
+    }
+
+    #[test]
+    fn test_pause_requires_guardian() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let minter = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        token.grant_role(&admin, &Role::Minter, &minter);
+
+        assert_eq!(
+            token.try_pause(&minter, &PauseScope::Mint),
+            Err(Ok(ExcellarError::Unauthorized.into()))
+        );
+    }
+
+    #[test]
+    fn test_pause_transfer() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user1 = Address::generate(&e);
+        let user2 = Address::generate(&e);
+        let user3 = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        token.pass_kyc(&admin, &user1, &KycTier::Retail);
+        token.pass_kyc(&admin, &user2, &KycTier::Retail);
+        token.mint(&admin, &user1, &1000);
+        token.approve(&user1, &user3, &500, &200);
+
+        token.pause(&admin, &PauseScope::Transfer);
+        assert_eq!(
+            token.try_transfer(&user1, &user2, &100),
+            Err(Ok(ExcellarError::Paused.into()))
+        );
+        assert_eq!(
+            token.try_transfer_from(&user3, &user1, &user2, &100),
+            Err(Ok(ExcellarError::Paused.into()))
+        );
+        // other scopes are unaffected
+        token.mint(&admin, &user2, &1000);
+        token.burn(&user2, &100);
+
+        token.unpause(&admin, &PauseScope::Transfer);
+        token.transfer(&user1, &user2, &100);
+        token.transfer_from(&user3, &user1, &user2, &100);
+        assert_eq!(token.balance(&user1), 800);
+        assert_eq!(token.balance(&user2), 1100);
+    }
+
+    #[test]
+    fn test_pause_mint() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+
+        token.pause(&admin, &PauseScope::Mint);
+        assert_eq!(
+            token.try_mint(&admin, &user, &1000),
+            Err(Ok(ExcellarError::Paused.into()))
+        );
+        token.unpause(&admin, &PauseScope::Mint);
+        token.mint(&admin, &user, &1000);
+        assert_eq!(token.balance(&user), 1000);
+    }
+
+    #[test]
+    fn test_pause_burn() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+        token.mint(&admin, &user, &1000);
+
+        token.pause(&admin, &PauseScope::Burn);
+        assert_eq!(
+            token.try_burn(&user, &100),
+            Err(Ok(ExcellarError::Paused.into()))
+        );
+        token.unpause(&admin, &PauseScope::Burn);
+        token.burn(&user, &100);
+        assert_eq!(token.balance(&user), 900);
+    }
+
+    #[test]
+    fn test_pause_claim() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        let blocks_per_reward: u32 = 28_800;
+        token.set_reward_tick(&admin, &blocks_per_reward);
+        token.set_reward_rate(&admin, &30_000_000);
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+        set_sequence_number(&e, 0);
+        token.mint(&admin, &user, &1000);
+        set_sequence_number(&e, blocks_per_reward);
+
+        token.pause(&admin, &PauseScope::Claim);
+        assert_eq!(
+            token.try_claim_reward(&user),
+            Err(Ok(ExcellarError::Paused.into()))
+        );
+        assert_eq!(
+            token.try_admin_claim_reward(&admin, &user),
+            Err(Ok(ExcellarError::Paused.into()))
+        );
+        token.unpause(&admin, &PauseScope::Claim);
+        token.claim_reward(&user);
+        assert_eq!(token.balance(&user), 1300);
+    }
+
+    #[test]
+    fn test_pause_approve() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let spender = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+
+        token.pause(&admin, &PauseScope::Approve);
+        assert_eq!(
+            token.try_approve(&user, &spender, &500, &200),
+            Err(Ok(ExcellarError::Paused.into()))
+        );
+        token.unpause(&admin, &PauseScope::Approve);
+        token.approve(&user, &spender, &500, &200);
+        assert_eq!(token.allowance(&user, &spender), 500);
+    }
+}
@@ -0,0 +1,91 @@
+use soroban_sdk::{panic_with_error, Address, Env};
+
+use crate::admin::{is_amm, is_blacklisted, is_kyc_passed};
+use crate::balance::read_frozen_balance;
+use crate::clock::accrual_now;
+use crate::eligibility::is_reward_eligible;
+use crate::error::ExcellarError;
+use crate::fixed_point::{checked_exp, mul_div, Rounding};
+use crate::reward::{
+    current_reward_index, get_frozen_reward_accrual, update_reward_index, GROWTH_PRECISION,
+};
+use crate::storage_types::{
+    DataKey, RebaseState, INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD,
+};
+
+pub fn read_rebase_state(e: &Env) -> Option<RebaseState> {
+    let key = DataKey::Rebase;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().get(&key)
+}
+
+pub fn is_rebasing(e: &Env) -> bool {
+    read_rebase_state(e).is_some()
+}
+
+/// Switches balances to shares. Existing balances become shares one to one
+/// and grow from here on, rewards accrued so far stay claimable.
+pub fn start_rebasing(e: &Env) -> RebaseState {
+    let index = update_reward_index(e);
+    let state = RebaseState {
+        started_at: accrual_now(e),
+        reward_index: index.value,
+        frozen_reward_index: index.frozen_value,
+    };
+    let key = DataKey::Rebase;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().set(&key, &state);
+    state
+}
+
+// Units a share is worth, scaled by `GROWTH_PRECISION`. Growth compounds
+// continuously on the reward index, so it only depends on the rates in force
+// and not on how often the index is updated.
+fn share_value(e: &Env, state: &RebaseState) -> i128 {
+    let growth = current_reward_index(e).value - state.reward_index;
+    match checked_exp(growth, GROWTH_PRECISION) {
+        Some(value) => value,
+        None => panic_with_error!(e, ExcellarError::ArithmeticOverflow),
+    }
+}
+
+pub fn shares_to_amount(e: &Env, state: &RebaseState, shares: i128) -> i128 {
+    mul_div(
+        e,
+        shares,
+        share_value(e, state),
+        GROWTH_PRECISION,
+        Rounding::Down,
+    )
+}
+
+pub fn amount_to_shares(e: &Env, state: &RebaseState, amount: i128, rounding: Rounding) -> i128 {
+    mul_div(e, amount, GROWTH_PRECISION, share_value(e, state), rounding)
+}
+
+/// Whether the balance of `addr` grows while rebasing, on the same terms
+/// that let it earn rewards otherwise. Pools never grow, their depositors do
+/// not earn on what they hold in a pool.
+pub fn earns_rebase_growth(e: &Env, addr: Address) -> bool {
+    !is_amm(e, addr.clone())
+        && is_kyc_passed(e, addr.clone())
+        && !is_blacklisted(e, addr.clone())
+        && is_reward_eligible(e, addr)
+}
+
+/// Part of a `balance` of `addr` that is held as a static amount instead of
+/// shares: all of it when the address does not grow, the frozen part while
+/// frozen balances do not accrue.
+pub fn static_balance_target(e: &Env, addr: Address, balance: i128) -> i128 {
+    if !earns_rebase_growth(e, addr.clone()) {
+        return balance;
+    }
+    if !get_frozen_reward_accrual(e) {
+        return read_frozen_balance(e, addr).min(balance);
+    }
+    0
+}
@@ -0,0 +1,212 @@
+use soroban_sdk::{contractclient, Address, Env};
+
+use crate::storage_types::{
+    DataKey, IdentityRegistryConfig, KycRecord, INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD,
+};
+
+/// Interface of an external identity registry shared by several tokens, so a
+/// single KYC or blacklist decision applies to all of them.
+#[contractclient(name = "IdentityRegistryClient")]
+pub trait IdentityRegistry {
+    fn kyc_record(env: Env, addr: Address) -> Option<KycRecord>;
+    fn is_blacklisted(env: Env, addr: Address) -> bool;
+}
+
+pub fn read_identity_registry(e: &Env) -> Option<IdentityRegistryConfig> {
+    let key = DataKey::IdentityRegistry;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().get(&key)
+}
+
+pub fn write_identity_registry(e: &Env, config: Option<IdentityRegistryConfig>) {
+    let key = DataKey::IdentityRegistry;
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    match config {
+        Some(config) => e.storage().instance().set(&key, &config),
+        None => e.storage().instance().remove(&key),
+    }
+}
+
+pub fn registry_kyc_record(
+    e: &Env,
+    config: &IdentityRegistryConfig,
+    addr: &Address,
+) -> Option<KycRecord> {
+    IdentityRegistryClient::new(e, &config.registry).kyc_record(addr)
+}
+
+pub fn registry_is_blacklisted(e: &Env, config: &IdentityRegistryConfig, addr: &Address) -> bool {
+    IdentityRegistryClient::new(e, &config.registry).is_blacklisted(addr)
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use soroban_sdk::{
+        contract, contractimpl, symbol_short, testutils::Address as _, Address, Env,
+    };
+
+    use crate::error::ExcellarError;
+    use crate::storage_types::{IdentityRegistryConfig, KycRecord, KycTier};
+
+    #[contract]
+    pub struct MockRegistry;
+
+    #[contractimpl]
+    impl MockRegistry {
+        pub fn set_kyc(e: Env, addr: Address, tier: KycTier, expires_at: u32) {
+            let record = KycRecord {
+                tier,
+                verified_at: e.ledger().sequence(),
+                expires_at,
+            };
+            e.storage()
+                .persistent()
+                .set(&(symbol_short!("kyc"), addr), &record);
+        }
+
+        pub fn set_blacklisted(e: Env, addr: Address, blacklisted: bool) {
+            e.storage()
+                .persistent()
+                .set(&(symbol_short!("black"), addr), &blacklisted);
+        }
+
+        pub fn kyc_record(e: Env, addr: Address) -> Option<KycRecord> {
+            e.storage().persistent().get(&(symbol_short!("kyc"), addr))
+        }
+
+        pub fn is_blacklisted(e: Env, addr: Address) -> bool {
+            e.storage()
+                .persistent()
+                .get(&(symbol_short!("black"), addr))
+                .unwrap_or(false)
+        }
+    }
+
+    fn create_registry<'a>(e: &Env) -> MockRegistryClient<'a> {
+        MockRegistryClient::new(e, &e.register_contract(None, MockRegistry {}))
+    }
+
+    #[test]
+    fn test_registry_shared_between_tokens() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let registry = create_registry(&e);
+        let token1 = crate::test::create_token(&e, &admin);
+        let token2 = crate::test::create_token(&e, &admin);
+        let config = IdentityRegistryConfig {
+            registry: registry.address.clone(),
+            local_fallback: false,
+        };
+        token1.set_identity_registry(&admin, &Some(config.clone()));
+        token2.set_identity_registry(&admin, &Some(config.clone()));
+        assert_eq!(token1.identity_registry(), Some(config));
+
+        assert_eq!(
+            token1.try_mint(&admin, &user, &1000),
+            Err(Ok(ExcellarError::KycNotPassed.into()))
+        );
+        registry.set_kyc(&user, &KycTier::Professional, &u32::MAX);
+        token1.mint(&admin, &user, &1000);
+        token2.mint(&admin, &user, &1000);
+        assert_eq!(
+            token1.kyc_status(&user).unwrap().tier,
+            KycTier::Professional
+        );
+
+        registry.set_blacklisted(&user, &true);
+        assert_eq!(
+            token1.try_mint(&admin, &user, &1000),
+            Err(Ok(ExcellarError::Blacklisted.into()))
+        );
+        assert_eq!(
+            token2.try_transfer(&user, &admin, &1),
+            Err(Ok(ExcellarError::Blacklisted.into()))
+        );
+    }
+
+    #[test]
+    fn test_registry_local_fallback() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let other = Address::generate(&e);
+        let registry = create_registry(&e);
+        let token = crate::test::create_token(&e, &admin);
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+
+        token.set_identity_registry(
+            &admin,
+            &Some(IdentityRegistryConfig {
+                registry: registry.address.clone(),
+                local_fallback: false,
+            }),
+        );
+        assert_eq!(token.kyc_status(&user), None);
+        assert_eq!(
+            token.try_mint(&admin, &user, &1000),
+            Err(Ok(ExcellarError::KycNotPassed.into()))
+        );
+        // local records are not read, so they cannot be changed either
+        assert_eq!(
+            token.try_pass_kyc(&admin, &user, &KycTier::Retail),
+            Err(Ok(ExcellarError::KycManagedByRegistry.into()))
+        );
+        assert_eq!(
+            token.try_fail_kyc(&admin, &user),
+            Err(Ok(ExcellarError::KycManagedByRegistry.into()))
+        );
+        // the local blacklist still applies on top of the registry
+        registry.set_kyc(&other, &KycTier::Retail, &u32::MAX);
+        token.blacklist(&admin, &other);
+        assert_eq!(
+            token.try_mint(&admin, &other, &1000),
+            Err(Ok(ExcellarError::Blacklisted.into()))
+        );
+        token.whitelist(&admin, &other);
+        token.mint(&admin, &other, &1000);
+        registry.set_blacklisted(&other, &true);
+        assert_eq!(
+            token.try_whitelist(&admin, &other),
+            Err(Ok(ExcellarError::Blacklisted.into()))
+        );
+
+        token.set_identity_registry(
+            &admin,
+            &Some(IdentityRegistryConfig {
+                registry: registry.address.clone(),
+                local_fallback: true,
+            }),
+        );
+        token.mint(&admin, &user, &1000);
+
+        // registry records take precedence over local ones
+        registry.set_kyc(&user, &KycTier::Institutional, &u32::MAX);
+        assert_eq!(
+            token.kyc_status(&user).unwrap().tier,
+            KycTier::Institutional
+        );
+
+        // the local blacklist applies regardless of the registry
+        token.blacklist(&admin, &user);
+        assert_eq!(
+            token.try_mint(&admin, &user, &1000),
+            Err(Ok(ExcellarError::Blacklisted.into()))
+        );
+        token.whitelist(&admin, &user);
+
+        token.set_identity_registry(&admin, &None);
+        assert_eq!(token.identity_registry(), None);
+        assert_eq!(token.kyc_status(&user).unwrap().tier, KycTier::Retail);
+    }
+}
@@ -1,69 +1,337 @@
-use crate::admin::{is_amm, is_kyc_passed};
+use crate::admin::{is_amm, is_blacklisted, is_kyc_passed, read_amm_list, report_kyc_expiry};
 use crate::amm::{calculate_amm_reward_share, get_amm_depositors};
-use soroban_sdk::{Address, Env};
+use soroban_sdk::{panic_with_error, Address, Env};
 
-use crate::balance::read_balance;
+use crate::balance::{
+    read_balance, read_frozen_balance, read_shares, read_static_balance, sync_static_balance,
+};
+use crate::clock::accrual_now;
 use crate::contract::check_non_negative_amount;
+use crate::eligibility::{is_reward_eligible, read_forgone_reward_treasury};
+use crate::error::ExcellarError;
+use crate::fixed_point::{mul_div, Rounding};
+use crate::funding::{
+    affordable_reward, can_receive_reward, is_reward_funding_source, pay_reward, read_reward_asset,
+};
+use crate::holder::add_holder;
+use crate::pause::is_paused;
+use crate::rebase::read_rebase_state;
+use crate::schedule::{read_rate_schedule, record_rate_change, write_rate_schedule};
 use crate::storage_types::{
-    AccumulatedReward, DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD,
-    INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD,
+    AccumulatedReward, DataKey, PauseScope, RateChange, RewardIndex, RewardInfo, RewardSnapshot,
+    BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT,
+    INSTANCE_LIFETIME_THRESHOLD,
 };
+use crate::tier::is_claim_allowed;
+
+// reward index is expressed as reward per unit of balance scaled by 1e18
+const INDEX_PRECISION: i128 = 1_000_000_000_000_000_000;
+// reward rate is expressed in 1e-8 units per tick
+const RATE_PRECISION: i128 = 100_000_000;
+
+pub(crate) const GROWTH_PRECISION: i128 = INDEX_PRECISION;
+
+pub(crate) fn index_delta(e: &Env, elapsed: u64, reward_rate: u32, reward_tick: u32) -> i128 {
+    mul_div(
+        e,
+        elapsed as i128 * reward_rate as i128,
+        INDEX_PRECISION / RATE_PRECISION,
+        reward_tick as i128,
+        Rounding::Down,
+    )
+}
 
-pub fn read_reward(e: &Env, addr: Address) -> i128 {
-    let key = DataKey::RewardCheckpoint(addr);
-    if let Some(reward) = e
+pub(crate) fn apply_index(e: &Env, balance: i128, index_delta: i128) -> i128 {
+    mul_div(e, balance, index_delta, INDEX_PRECISION, Rounding::HalfUp)
+}
+
+fn advance_index(
+    e: &Env,
+    index: &mut RewardIndex,
+    at: u64,
+    reward_rate: u32,
+    reward_tick: u32,
+    frozen_accrual: bool,
+) {
+    let delta = index_delta(e, at - index.last_accrued_at, reward_rate, reward_tick);
+    index.value += delta;
+    if frozen_accrual {
+        index.frozen_value += delta;
+    }
+    index.last_accrued_at = at;
+}
+
+// Integrates the index up to now across every scheduled rate
+// change that fell due since it was last updated, returning the due changes.
+fn accrue_reward_index(e: &Env) -> (RewardIndex, u32) {
+    let key = DataKey::RewardIndex;
+    let now = accrual_now(e);
+    let Some(mut index) = e.storage().persistent().get::<DataKey, RewardIndex>(&key) else {
+        let index = RewardIndex {
+            value: 0,
+            frozen_value: 0,
+            last_accrued_at: now,
+            started_at: now,
+            start_rate: get_reward_rate(e),
+            start_tick: get_reward_tick(e),
+        };
+        return (index, 0);
+    };
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+    let frozen_accrual = get_frozen_reward_accrual(e);
+    let mut rate = get_reward_rate(e);
+    let mut tick = get_reward_tick(e);
+    let mut due = 0;
+    for change in read_rate_schedule(e).iter() {
+        if change.effective_at > now {
+            break;
+        }
+        if change.effective_at > index.last_accrued_at {
+            advance_index(
+                e,
+                &mut index,
+                change.effective_at,
+                rate,
+                tick,
+                frozen_accrual,
+            );
+        }
+        rate = change.rate;
+        tick = change.tick;
+        due += 1;
+    }
+    if now > index.last_accrued_at {
+        advance_index(e, &mut index, now, rate, tick, frozen_accrual);
+    }
+    (index, due)
+}
+
+/// Returns the reward index as of now without persisting it.
+pub fn current_reward_index(e: &Env) -> RewardIndex {
+    accrue_reward_index(e).0
+}
+
+/// Accrues the reward index up to now and applies scheduled rate changes that
+/// fell due, must run before the rate or tick changes.
+pub fn update_reward_index(e: &Env) -> RewardIndex {
+    let key = DataKey::RewardIndex;
+    let (index, due) = accrue_reward_index(e);
+    if due > 0 {
+        let mut schedule = read_rate_schedule(e);
+        for _ in 0..due {
+            let change = schedule.pop_front_unchecked();
+            write_reward_rate(e, change.rate);
+            write_reward_tick(e, change.tick);
+            record_rate_change(e, change);
+        }
+        write_rate_schedule(e, &schedule);
+    }
+    e.storage().persistent().set(&key, &index);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    index
+}
+
+// Balance rewards accrue on and the frozen part of it. Once rebasing, rewards
+// only accrue up to the switch, on the balance held at that point which is
+// what the shares still record. Both are counted in shares as of the switch,
+// when a share was worth one unit: an address keeps the shares and frozen
+// amount it had until it is first checkpointed after the switch, which
+// settles that accrual for good.
+fn accruing_balance(e: &Env, addr: Address) -> (i128, i128) {
+    let frozen = read_frozen_balance(e, addr.clone());
+    match read_rebase_state(e) {
+        Some(_) => {
+            let held = read_shares(e, addr.clone()) + read_static_balance(e, addr);
+            (held, frozen.min(held))
+        }
+        None => (read_balance(e, addr), frozen),
+    }
+}
+
+fn reward_balance(e: &Env, addr: Address) -> i128 {
+    let (balance, frozen) = accruing_balance(e, addr);
+    if !get_frozen_reward_accrual(e) {
+        return balance - frozen;
+    }
+    balance
+}
+
+// snapshot of the address without writing storage, a record of the
+// per-account accrual model stands in for it until a checkpoint migrates it
+fn peek_snapshot(e: &Env, addr: Address) -> Option<RewardSnapshot> {
+    let key = DataKey::RewardSnapshot(addr.clone());
+    if let Some(snapshot) = e
         .storage()
         .persistent()
-        .get::<DataKey, AccumulatedReward>(&key)
+        .get::<DataKey, RewardSnapshot>(&key)
     {
         e.storage()
             .persistent()
             .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
-        reward.amount
-    } else {
-        0
+        return Some(snapshot);
+    }
+    legacy_snapshot(e, addr, &current_reward_index(e))
+}
+
+fn write_snapshot(e: &Env, addr: Address, snapshot: &RewardSnapshot) {
+    let key = DataKey::RewardSnapshot(addr);
+    e.storage().persistent().set(&key, snapshot);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+// replaces a record of the per-account accrual model with a snapshot, a
+// record some write already superseded is only dropped
+fn migrate_accumulated_reward(e: &Env, addr: Address) {
+    let legacy_key = DataKey::RewardCheckpoint(addr.clone());
+    if !e.storage().persistent().has(&legacy_key) {
+        return;
+    }
+    let snapshot_key = DataKey::RewardSnapshot(addr.clone());
+    if !e.storage().persistent().has(&snapshot_key) {
+        if let Some(snapshot) = legacy_snapshot(e, addr.clone(), &update_reward_index(e)) {
+            write_snapshot(e, addr, &snapshot);
+        }
+    }
+    e.storage().persistent().remove(&legacy_key);
+}
+
+fn legacy_snapshot(e: &Env, addr: Address, index: &RewardIndex) -> Option<RewardSnapshot> {
+    let legacy = e
+        .storage()
+        .persistent()
+        .get::<DataKey, AccumulatedReward>(&DataKey::RewardCheckpoint(addr.clone()))?;
+
+    // the old record accrued up to when the index started, the index covers
+    // everything after that
+    let blocks_held = index
+        .started_at
+        .saturating_sub(legacy.last_ledger_number.into());
+    let settled = _calculate_reward(
+        e,
+        blocks_held,
+        reward_balance(e, addr.clone()),
+        index.start_rate,
+        index.start_tick,
+    );
+    Some(RewardSnapshot {
+        created_at: legacy.created_ledger_number.into(),
+        last_accrued_at: index.started_at,
+        index: 0,
+        frozen_index: 0,
+        amount: legacy.amount + settled,
+    })
+}
+
+pub fn read_reward(e: &Env, addr: Address) -> i128 {
+    match peek_snapshot(e, addr) {
+        Some(snapshot) => snapshot.amount,
+        None => 0,
     }
 }
 
 fn write_reward(e: &Env, addr: Address, amount: i128) {
-    check_non_negative_amount(amount);
-
-    let key = DataKey::RewardCheckpoint(addr);
-    let existing_reward: Option<AccumulatedReward> = e.storage().persistent().get(&key);
-    match existing_reward {
-        Some(reward) => {
-            let acc_reward = AccumulatedReward {
-                created_ledger_number: reward.created_ledger_number,
-                last_ledger_number: e.ledger().sequence(),
-                amount: amount + reward.amount,
-            };
-            e.storage().persistent().set(&key, &acc_reward);
-            e.storage().persistent().extend_ttl(
-                &key,
-                BALANCE_LIFETIME_THRESHOLD,
-                BALANCE_BUMP_AMOUNT,
-            );
+    check_non_negative_amount(e, amount);
+
+    let index = update_reward_index(e);
+    let snapshot = match peek_snapshot(e, addr.clone()) {
+        Some(snapshot) => RewardSnapshot {
+            created_at: snapshot.created_at,
+            last_accrued_at: accrual_now(e),
+            index: index.value,
+            frozen_index: index.frozen_value,
+            amount: amount + snapshot.amount,
+        },
+        None => RewardSnapshot {
+            created_at: accrual_now(e),
+            last_accrued_at: accrual_now(e),
+            index: index.value,
+            frozen_index: index.frozen_value,
+            amount,
+        },
+    };
+    write_snapshot(e, addr, &snapshot);
+}
+
+// credits reward earned elsewhere without settling the account's own accrual
+fn credit_reward(e: &Env, addr: Address, amount: i128) {
+    check_non_negative_amount(e, amount);
+    if amount > 0 {
+        add_holder(e, addr.clone());
+    }
+
+    let snapshot = match peek_snapshot(e, addr.clone()) {
+        Some(mut snapshot) => {
+            snapshot.amount += amount;
+            snapshot
         }
         None => {
-            let acc_reward = AccumulatedReward {
-                created_ledger_number: e.ledger().sequence(),
-                last_ledger_number: e.ledger().sequence(),
+            let index = update_reward_index(e);
+            RewardSnapshot {
+                created_at: accrual_now(e),
+                last_accrued_at: accrual_now(e),
+                index: index.value,
+                frozen_index: index.frozen_value,
                 amount,
-            };
-            e.storage().persistent().set(&key, &acc_reward)
+            }
         }
+    };
+    write_snapshot(e, addr, &snapshot);
+}
+
+pub fn restart_reward_accrual(e: &Env, addr: Address) {
+    if let Some(mut snapshot) = peek_snapshot(e, addr.clone()) {
+        let index = update_reward_index(e);
+        snapshot.index = index.value;
+        snapshot.frozen_index = index.frozen_value;
+        snapshot.last_accrued_at = accrual_now(e);
+        write_snapshot(e, addr, &snapshot);
     }
-    e.storage()
-        .persistent()
-        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
 }
 
 pub fn reset_reward(e: &Env, addr: Address) {
-    let key = DataKey::RewardCheckpoint(addr);
-    e.storage().persistent().remove(&key);
+    if let Some(mut snapshot) = peek_snapshot(e, addr.clone()) {
+        let index = update_reward_index(e);
+        snapshot.index = index.value;
+        snapshot.frozen_index = index.frozen_value;
+        snapshot.last_accrued_at = accrual_now(e);
+        snapshot.amount = 0;
+        write_snapshot(e, addr, &snapshot);
+    }
+}
+
+// takes a payout off the settled reward, whatever was not paid stays claimable
+pub fn deduct_reward(e: &Env, addr: Address, amount: i128) {
+    if let Some(mut snapshot) = peek_snapshot(e, addr.clone()) {
+        let index = update_reward_index(e);
+        snapshot.index = index.value;
+        snapshot.frozen_index = index.frozen_value;
+        snapshot.last_accrued_at = accrual_now(e);
+        snapshot.amount -= amount;
+        write_snapshot(e, addr, &snapshot);
+    }
 }
 
 pub fn set_reward_rate(e: &Env, rate: u32) {
+    update_reward_index(e);
+    write_reward_rate(e, rate);
+    record_rate_change(
+        e,
+        RateChange {
+            effective_at: accrual_now(e),
+            rate,
+            tick: get_reward_tick(e),
+        },
+    );
+}
+
+fn write_reward_rate(e: &Env, rate: u32) {
     let key = DataKey::RewardRate;
     let rate = rate.max(0);
     e.storage().persistent().set(&key, &rate);
@@ -86,7 +354,39 @@ pub fn get_reward_rate(e: &Env) -> u32 {
     }
 }
 
+/// Rate and tick in force right now, including scheduled changes that fell
+/// due but were not applied yet.
+pub fn current_reward_rate(e: &Env) -> (u32, u32) {
+    let now = accrual_now(e);
+    let (mut rate, mut tick) = (get_reward_rate(e), get_reward_tick(e));
+    for change in read_rate_schedule(e).iter() {
+        if change.effective_at > now {
+            break;
+        }
+        rate = change.rate;
+        tick = change.tick;
+    }
+    (rate, tick)
+}
+
 pub fn set_reward_tick(e: &Env, tick: u32) {
+    // the index divides by the tick
+    if tick == 0 {
+        panic_with_error!(e, ExcellarError::InvalidRewardTick)
+    }
+    update_reward_index(e);
+    write_reward_tick(e, tick);
+    record_rate_change(
+        e,
+        RateChange {
+            effective_at: accrual_now(e),
+            rate: get_reward_rate(e),
+            tick,
+        },
+    );
+}
+
+fn write_reward_tick(e: &Env, tick: u32) {
     let tick = tick.max(0);
     let key = DataKey::RewardTick;
     e.storage().persistent().set(&key, &tick);
@@ -110,22 +410,155 @@ pub fn get_reward_tick(e: &Env) -> u32 {
     }
 }
 
+// accrual up to here is settled on the index under the previous setting, so
+// the change only applies from the current ledger on
+pub fn set_frozen_reward_accrual(e: &Env, enabled: bool) {
+    update_reward_index(e);
+    let key = DataKey::FrozenRewardAccrual;
+    e.storage().persistent().set(&key, &enabled);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+pub fn get_frozen_reward_accrual(e: &Env) -> bool {
+    let key = DataKey::FrozenRewardAccrual;
+    if let Some(enabled) = e.storage().persistent().get::<DataKey, bool>(&key) {
+        e.storage().persistent().extend_ttl(
+            &key,
+            INSTANCE_LIFETIME_THRESHOLD,
+            INSTANCE_BUMP_AMOUNT,
+        );
+        enabled
+    } else {
+        // frozen funds earn rewards unless configured otherwise
+        true
+    }
+}
+
+pub fn set_default_auto_compound(e: &Env, enabled: bool) {
+    let key = DataKey::AutoCompound;
+    e.storage().persistent().set(&key, &enabled);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+pub fn get_default_auto_compound(e: &Env) -> bool {
+    let key = DataKey::AutoCompound;
+    if let Some(enabled) = e.storage().persistent().get::<DataKey, bool>(&key) {
+        e.storage().persistent().extend_ttl(
+            &key,
+            INSTANCE_LIFETIME_THRESHOLD,
+            INSTANCE_BUMP_AMOUNT,
+        );
+        enabled
+    } else {
+        false
+    }
+}
+
+pub fn write_auto_compound(e: &Env, addr: Address, enabled: bool) {
+    let key = DataKey::AutoCompoundAccount(addr);
+    e.storage().persistent().set(&key, &enabled);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+pub fn read_reward_claimer(e: &Env, owner: Address) -> Option<Address> {
+    let key = DataKey::RewardClaimer(owner);
+    if let Some(claimer) = e.storage().persistent().get::<DataKey, Address>(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        Some(claimer)
+    } else {
+        None
+    }
+}
+
+pub fn write_reward_claimer(e: &Env, owner: Address, claimer: Option<Address>) {
+    let key = DataKey::RewardClaimer(owner);
+    match claimer {
+        Some(claimer) => {
+            e.storage().persistent().set(&key, &claimer);
+            e.storage().persistent().extend_ttl(
+                &key,
+                BALANCE_LIFETIME_THRESHOLD,
+                BALANCE_BUMP_AMOUNT,
+            );
+        }
+        None => e.storage().persistent().remove(&key),
+    }
+}
+
+/// An account's own choice takes precedence over the default.
+pub fn is_auto_compound(e: &Env, addr: Address) -> bool {
+    let key = DataKey::AutoCompoundAccount(addr);
+    if let Some(enabled) = e.storage().persistent().get::<DataKey, bool>(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        enabled
+    } else {
+        get_default_auto_compound(e)
+    }
+}
+
+// pays the settled reward into the balance, subject to the same conditions
+// as a claim; otherwise the reward stays claimable. Never fails on a funding
+// shortfall, it compounds what is available and leaves the rest. Rewards paid
+// in another asset cannot be compounded.
+fn compound_reward(e: &Env, addr: Address) {
+    if is_amm(e, addr.clone())
+        || read_reward_asset(e).is_some()
+        || is_reward_funding_source(e, addr.clone())
+        || !is_auto_compound(e, addr.clone())
+        || is_paused(e, PauseScope::Claim)
+        || !is_claim_allowed(e, addr.clone())
+    {
+        return;
+    }
+    // compounds what a claim would pay, nothing at all on a shortfall the
+    // policy does not allow paying partially
+    let Some(reward) = affordable_reward(e, read_reward(e, addr.clone())) else {
+        return;
+    };
+    if reward < 1 || !can_receive_reward(e, addr.clone(), reward) {
+        return;
+    }
+    let paid = pay_reward(e, e.current_contract_address(), addr.clone(), reward);
+    deduct_reward(e, addr, paid);
+}
+
 pub fn calculate_reward(e: &Env, addr: Address) -> i128 {
-    let key = DataKey::RewardCheckpoint(addr.clone());
-    let reward_checkpoint: Option<AccumulatedReward> = e.storage().persistent().get(&key);
-    let blocks_held = match reward_checkpoint {
-        Some(checkpoint) => e.ledger().sequence() - checkpoint.last_ledger_number,
-        None => 0,
+    let Some(snapshot) = peek_snapshot(e, addr.clone()) else {
+        return 0;
     };
-    let balance = read_balance(e, addr.clone());
-    let reward_rate = get_reward_rate(e);
-    let reward_tick = get_reward_tick(e);
+    let index = current_reward_index(e);
+    let (mut value, mut frozen_value) = (index.value, index.frozen_value);
+    if let Some(state) = read_rebase_state(e) {
+        value = value.min(state.reward_index);
+        frozen_value = frozen_value.min(state.frozen_reward_index);
+    }
 
-    _calculate_reward(blocks_held, balance, reward_rate, reward_tick)
+    // the frozen part of the balance earns on its own index, which only
+    // advances while frozen balances accrue
+    let (balance, frozen) = accruing_balance(e, addr);
+    let mut reward = 0;
+    if value > snapshot.index {
+        reward += apply_index(e, balance - frozen, value - snapshot.index);
+    }
+    if frozen_value > snapshot.frozen_index {
+        reward += apply_index(e, frozen, frozen_value - snapshot.frozen_index);
+    }
+    reward
 }
 
 pub fn _calculate_reward(
-    blocks_held: u32,
+    e: &Env,
+    blocks_held: u64,
     balance: i128,
     reward_rate: u32,
     reward_tick: u32,
@@ -135,37 +568,146 @@ pub fn _calculate_reward(
 
     let reward_rate_fp = (reward_rate as i128 * scale_factor) / ten_thousand_basis_points;
     let holding_period_fp = (blocks_held as i128 * scale_factor) / reward_tick as i128;
-    let reward_numerator = balance * reward_rate_fp * holding_period_fp;
-    // Apply a rounding adjustment before the final division to ensure results close to .5 round up
-    // We scale the numerator up further by the scale factor to ensure division is the last operation
-    // This maximizes precision before rounding takes effect
-    let rounded_numerator = reward_numerator + (scale_factor * scale_factor / 2);
-
-    rounded_numerator / (scale_factor * scale_factor)
+    let Some(growth) = reward_rate_fp.checked_mul(holding_period_fp) else {
+        panic_with_error!(e, ExcellarError::ArithmeticOverflow)
+    };
+    // results close to .5 round up, the product is carried in 256 bits so
+    // large balances cannot overflow it
+    mul_div(
+        e,
+        balance,
+        growth,
+        scale_factor * scale_factor,
+        Rounding::HalfUp,
+    )
 }
 
+/// Settles the reward of `address` and, while rebasing, brings its balance in
+/// line with whether it grows.
 pub fn checkpoint_reward(e: &Env, address: Address) {
+    migrate_accumulated_reward(e, address.clone());
+    settle_reward(e, address.clone());
+    sync_static_balance(e, address);
+}
+
+fn settle_reward(e: &Env, address: Address) {
     if !is_kyc_passed(e, address.clone()) && !is_amm(e, address.clone()) {
+        report_kyc_expiry(e, address);
+        return;
+    }
+    // accrual stays frozen from the ledger the address was blacklisted
+    if is_blacklisted(e, address.clone()) {
         return;
     }
 
     let total_reward = calculate_reward(e, address.clone());
+    if !is_reward_eligible(e, address.clone()) {
+        forgo_reward(e, address, total_reward);
+        return;
+    }
     write_reward(e, address.clone(), total_reward);
+    compound_reward(e, address.clone());
 
     if is_amm(e, address.clone()) {
         if let Some(depositors) = get_amm_depositors(e, address) {
             let total_balance: i128 = depositors.iter().map(|d| d.balance).sum();
             if total_balance > 0 {
                 for depositor in depositors.iter() {
-                    let reward =
-                        calculate_amm_reward_share(total_reward, depositor.balance, total_balance);
-                    write_reward(e, depositor.depositor, reward);
+                    if is_blacklisted(e, depositor.depositor.clone()) {
+                        continue;
+                    }
+                    let reward = calculate_amm_reward_share(
+                        e,
+                        total_reward,
+                        depositor.balance,
+                        total_balance,
+                    );
+                    if is_reward_eligible(e, depositor.depositor.clone()) {
+                        credit_reward(e, depositor.depositor, reward);
+                    } else {
+                        redirect_reward(e, reward);
+                    }
                 }
             }
         }
     }
 }
 
+// an ineligible address earns nothing from here, what it would have earned
+// goes to the forgone reward treasury when there is one
+fn forgo_reward(e: &Env, addr: Address, reward: i128) {
+    restart_reward_accrual(e, addr);
+    redirect_reward(e, reward);
+}
+
+/// Hands the settled reward of `addr` to the forgone reward treasury, or
+/// drops it when there is none, returning the amount.
+pub fn forfeit_reward_to_treasury(e: &Env, addr: Address) -> i128 {
+    let reward = read_reward(e, addr.clone());
+    reset_reward(e, addr);
+    redirect_reward(e, reward);
+    reward
+}
+
+fn redirect_reward(e: &Env, reward: i128) {
+    if reward < 1 {
+        return;
+    }
+    if let Some(treasury) = read_forgone_reward_treasury(e) {
+        credit_reward(e, treasury, reward);
+    }
+}
+
+/// Reward `checkpoint_reward` would settle for the address right now,
+/// including its share of every pool it deposited in, without writing storage.
+pub fn unsettled_reward(e: &Env, address: Address) -> i128 {
+    if is_blacklisted(e, address.clone()) || !is_reward_eligible(e, address.clone()) {
+        return 0;
+    }
+    let mut reward = 0;
+    if is_kyc_passed(e, address.clone()) || is_amm(e, address.clone()) {
+        reward += calculate_reward(e, address.clone());
+    }
+
+    for amm in read_amm_list(e).iter() {
+        if amm == address || is_blacklisted(e, amm.clone()) || !is_reward_eligible(e, amm.clone()) {
+            continue;
+        }
+        let Some(depositors) = get_amm_depositors(e, amm.clone()) else {
+            continue;
+        };
+        let Some(depositor) = depositors.iter().find(|d| d.depositor == address) else {
+            continue;
+        };
+        let total_balance: i128 = depositors.iter().map(|d| d.balance).sum();
+        reward += calculate_amm_reward_share(
+            e,
+            calculate_reward(e, amm),
+            depositor.balance,
+            total_balance,
+        );
+    }
+    reward
+}
+
+pub fn read_reward_info(e: &Env, addr: Address) -> RewardInfo {
+    let pending = unsettled_reward(e, addr.clone());
+    match peek_snapshot(e, addr) {
+        Some(snapshot) => RewardInfo {
+            created_at: snapshot.created_at,
+            last_accrued_at: snapshot.last_accrued_at,
+            amount: snapshot.amount,
+            pending,
+        },
+        None => RewardInfo {
+            created_at: 0,
+            last_accrued_at: 0,
+            amount: 0,
+            pending,
+        },
+    }
+}
+
 #[cfg(test)]
 mod test {
     extern crate std;
@@ -173,7 +715,10 @@ mod test {
     use soroban_sdk::testutils::Address as _;
     use soroban_sdk::{Address, Env};
 
+    use crate::error::ExcellarError;
     use crate::reward::{_calculate_reward, set_reward_rate, set_reward_tick};
+    use crate::storage_types::{AccumulatedReward, DataKey, KycTier};
+    use crate::test::set_sequence_number;
 
     fn setup_test_env() -> (Env, Address) {
         let env = Env::default();
@@ -188,6 +733,7 @@ mod test {
 
     #[test]
     fn test_reward_calculation_per_block_tick() {
+        let e = Env::default();
         // means 5%
         let reward_rate = 5_000_000;
         let reward_tick = 1;
@@ -195,14 +741,14 @@ mod test {
         let blocks_held = 10;
         let balance = 1000;
 
-        let result = _calculate_reward(blocks_held, balance, reward_rate, reward_tick);
+        let result = _calculate_reward(&e, blocks_held, balance, reward_rate, reward_tick);
 
         assert_eq!(result, 500, "Rounding error in _calculate_reward function");
 
         let blocks_held = 1_000_000;
         let balance = 1_000_000_000;
 
-        let result = _calculate_reward(blocks_held, balance, reward_rate, reward_tick);
+        let result = _calculate_reward(&e, blocks_held, balance, reward_rate, reward_tick);
         assert_eq!(
             result, 50_000_000_000_000,
             "Rounding error in _calculate_reward function"
@@ -211,6 +757,7 @@ mod test {
 
     #[test]
     fn test_reward_calculation_per_day_tick() {
+        let e = Env::default();
         // means 0.00005bps
         let reward_rate = 5;
         let reward_tick = 28_800;
@@ -218,15 +765,118 @@ mod test {
         let blocks_held = 287;
         let balance = 1_000_000_000;
 
-        let result = _calculate_reward(blocks_held, balance, reward_rate, reward_tick);
+        let result = _calculate_reward(&e, blocks_held, balance, reward_rate, reward_tick);
         assert_eq!(result, 0);
 
         let blocks_held = 288;
-        let result = _calculate_reward(blocks_held, balance, reward_rate, reward_tick);
+        let result = _calculate_reward(&e, blocks_held, balance, reward_rate, reward_tick);
         assert_eq!(result, 1);
 
         let balance = 10_000_000_000;
-        let result = _calculate_reward(blocks_held, balance, reward_rate, reward_tick);
+        let result = _calculate_reward(&e, blocks_held, balance, reward_rate, reward_tick);
         assert_eq!(result, 5);
     }
+
+    #[test]
+    fn test_rate_change_does_not_reprice_history() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        let blocks_per_reward: u32 = 28_800;
+        token.set_reward_tick(&admin, &blocks_per_reward);
+        // 1% per cycle
+        token.set_reward_rate(&admin, &1_000_000);
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+        set_sequence_number(&e, 0);
+        token.mint(&admin, &user, &1000);
+
+        set_sequence_number(&e, blocks_per_reward);
+        token.set_reward_rate(&admin, &2_000_000);
+        set_sequence_number(&e, blocks_per_reward * 2);
+        token.set_reward_rate(&admin, &0);
+        set_sequence_number(&e, blocks_per_reward * 3);
+
+        token.claim_reward(&user);
+        assert_eq!(token.balance(&user), 1030);
+
+        // accrual continues after a claim without another touch
+        token.set_reward_rate(&admin, &1_000_000);
+        set_sequence_number(&e, blocks_per_reward * 4);
+        token.claim_reward(&user);
+        assert_eq!(token.balance(&user), 1040);
+    }
+
+    #[test]
+    fn test_zero_reward_tick_is_rejected() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        assert_eq!(
+            token.try_set_reward_tick(&admin, &0),
+            Err(Ok(ExcellarError::InvalidRewardTick.into()))
+        );
+        let history = token.reward_rate_history(&0, &u64::MAX);
+        assert_eq!(history.last().unwrap().tick, 28_800);
+    }
+
+    #[test]
+    fn test_migrate_accumulated_reward() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        let blocks_per_reward: u32 = 28_800;
+        token.set_reward_tick(&admin, &blocks_per_reward);
+        token.set_reward_rate(&admin, &1_000_000);
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+        set_sequence_number(&e, 0);
+        token.mint(&admin, &user, &1000);
+
+        // state as left by the per-account accrual model
+        e.as_contract(&token.address, || {
+            e.storage().persistent().remove(&DataKey::RewardIndex);
+            e.storage()
+                .persistent()
+                .remove(&DataKey::RewardSnapshot(user.clone()));
+            e.storage().persistent().set(
+                &DataKey::RewardCheckpoint(user.clone()),
+                &AccumulatedReward {
+                    created_ledger_number: 0,
+                    last_ledger_number: 0,
+                    amount: 5,
+                },
+            );
+        });
+
+        // reading the reward leaves the old record in place
+        set_sequence_number(&e, blocks_per_reward);
+        assert_eq!(token.get_reward(&user), 15);
+        e.as_contract(&token.address, || {
+            assert!(e
+                .storage()
+                .persistent()
+                .has(&DataKey::RewardCheckpoint(user.clone())));
+        });
+
+        // the next checkpoint migrates it
+        token.transfer(&user, &user, &0);
+        assert_eq!(token.get_reward(&user), 15);
+        e.as_contract(&token.address, || {
+            assert!(!e
+                .storage()
+                .persistent()
+                .has(&DataKey::RewardCheckpoint(user.clone())));
+        });
+
+        set_sequence_number(&e, blocks_per_reward * 2);
+        token.claim_reward(&user);
+        assert_eq!(token.balance(&user), 1025);
+    }
 }
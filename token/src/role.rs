@@ -0,0 +1,233 @@
+use soroban_sdk::{panic_with_error, Address, Env};
+
+use crate::admin::read_administrator;
+use crate::error::ExcellarError;
+use crate::storage_types::{
+    DataKey, Role, RoleDataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD,
+    INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD,
+};
+
+pub fn has_role(e: &Env, role: Role, addr: Address) -> bool {
+    // the administrator implicitly holds every role
+    if read_administrator(e) == addr {
+        return true;
+    }
+    if role == Role::Admin {
+        return false;
+    }
+
+    let key = DataKey::Role(RoleDataKey { role, addr });
+    if let Some(val) = e.storage().persistent().get(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        return val;
+    }
+    false
+}
+
+pub fn write_role(e: &Env, role: Role, addr: Address) {
+    if role == Role::Admin {
+        panic_with_error!(e, ExcellarError::InvalidRole)
+    }
+    let key = DataKey::Role(RoleDataKey { role, addr });
+    e.storage().persistent().set(&key, &true);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+pub fn remove_role(e: &Env, role: Role, addr: Address) {
+    if role == Role::Admin {
+        panic_with_error!(e, ExcellarError::InvalidRole)
+    }
+    let key = DataKey::Role(RoleDataKey { role, addr });
+    e.storage().persistent().remove(&key);
+}
+
+pub fn read_role_admin(e: &Env, role: Role) -> Role {
+    let key = DataKey::RoleAdmin(role);
+    if let Some(admin_role) = e.storage().persistent().get::<DataKey, Role>(&key) {
+        e.storage().persistent().extend_ttl(
+            &key,
+            INSTANCE_LIFETIME_THRESHOLD,
+            INSTANCE_BUMP_AMOUNT,
+        );
+        admin_role
+    } else {
+        Role::Admin
+    }
+}
+
+pub fn write_role_admin(e: &Env, role: Role, admin_role: Role) {
+    if role == Role::Admin {
+        panic_with_error!(e, ExcellarError::InvalidRole)
+    }
+    let key = DataKey::RoleAdmin(role);
+    e.storage().persistent().set(&key, &admin_role);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+pub fn require_role(e: &Env, role: Role, addr: Address) {
+    addr.require_auth();
+    if !has_role(e, role, addr) {
+        panic_with_error!(e, ExcellarError::Unauthorized)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use soroban_sdk::{
+        symbol_short,
+        testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation},
+        Address, Env, IntoVal, Symbol,
+    };
+
+    use crate::error::ExcellarError;
+    use crate::storage_types::{KycTier, Role};
+
+    #[test]
+    fn test_grant_role() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let minter = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+
+        token.grant_role(&admin, &Role::Minter, &minter);
+        assert_eq!(
+            e.auths(),
+            std::vec![(
+                admin.clone(),
+                AuthorizedInvocation {
+                    function: AuthorizedFunction::Contract((
+                        token.address.clone(),
+                        Symbol::new(&e, "grant_role"),
+                        (&admin, Role::Minter, &minter).into_val(&e),
+                    )),
+                    sub_invocations: std::vec![],
+                }
+            )]
+        );
+        assert!(token.has_role(&Role::Minter, &minter));
+        assert!(!token.has_role(&Role::Compliance, &minter));
+        assert!(token.has_role(&Role::Minter, &admin));
+    }
+
+    #[test]
+    fn test_minter_cannot_manage_kyc() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let minter = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+
+        token.grant_role(&admin, &Role::Minter, &minter);
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+        token.mint(&minter, &user, &1000);
+        assert_eq!(
+            e.auths(),
+            std::vec![(
+                minter.clone(),
+                AuthorizedInvocation {
+                    function: AuthorizedFunction::Contract((
+                        token.address.clone(),
+                        symbol_short!("mint"),
+                        (&minter, &user, 1000_i128).into_val(&e),
+                    )),
+                    sub_invocations: std::vec![],
+                }
+            )]
+        );
+        assert_eq!(token.balance(&user), 1000);
+
+        assert_eq!(
+            token.try_fail_kyc(&minter, &user),
+            Err(Ok(ExcellarError::Unauthorized.into()))
+        );
+        assert_eq!(
+            token.try_set_reward_rate(&minter, &1),
+            Err(Ok(ExcellarError::Unauthorized.into()))
+        );
+    }
+
+    #[test]
+    fn test_compliance_cannot_mint() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let officer = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+
+        token.grant_role(&admin, &Role::Compliance, &officer);
+        token.pass_kyc(&officer, &user, &KycTier::Retail);
+        token.blacklist(&officer, &user);
+        token.whitelist(&officer, &user);
+
+        assert_eq!(
+            token.try_mint(&officer, &user, &1000),
+            Err(Ok(ExcellarError::Unauthorized.into()))
+        );
+
+        token.revoke_role(&admin, &Role::Compliance, &officer);
+        assert!(!token.has_role(&Role::Compliance, &officer));
+        assert_eq!(
+            token.try_fail_kyc(&officer, &user),
+            Err(Ok(ExcellarError::Unauthorized.into()))
+        );
+    }
+
+    #[test]
+    fn test_role_admin_hierarchy() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let manager = Address::generate(&e);
+        let minter = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+
+        assert_eq!(token.role_admin(&Role::Minter), Role::Admin);
+        token.grant_role(&admin, &Role::RewardManager, &manager);
+        assert_eq!(
+            token.try_grant_role(&manager, &Role::Minter, &minter),
+            Err(Ok(ExcellarError::Unauthorized.into()))
+        );
+
+        assert_eq!(
+            token.try_set_role_admin(&manager, &Role::Minter, &Role::RewardManager),
+            Err(Ok(ExcellarError::Unauthorized.into()))
+        );
+        token.set_role_admin(&admin, &Role::Minter, &Role::RewardManager);
+        assert_eq!(token.role_admin(&Role::Minter), Role::RewardManager);
+        token.grant_role(&manager, &Role::Minter, &minter);
+        assert!(token.has_role(&Role::Minter, &minter));
+
+        token.renounce_role(&minter, &Role::Minter);
+        assert!(!token.has_role(&Role::Minter, &minter));
+    }
+
+    #[test]
+    fn test_admin_role_cannot_be_granted() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+
+        assert_eq!(
+            token.try_grant_role(&admin, &Role::Admin, &user),
+            Err(Ok(ExcellarError::InvalidRole.into()))
+        );
+    }
+}
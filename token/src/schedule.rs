@@ -0,0 +1,298 @@
+use soroban_sdk::{panic_with_error, Env, Vec};
+
+use crate::clock::accrual_now;
+use crate::error::ExcellarError;
+use crate::storage_types::{
+    DataKey, RateChange, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT,
+    INSTANCE_LIFETIME_THRESHOLD,
+};
+
+// the schedule is a single storage entry read on every index update
+pub const MAX_SCHEDULED_RATE_CHANGES: u32 = 16;
+
+/// Pending rate changes, ordered by when they take effect.
+pub fn read_rate_schedule(e: &Env) -> Vec<RateChange> {
+    let key = DataKey::RewardRateSchedule;
+    if let Some(schedule) = e
+        .storage()
+        .persistent()
+        .get::<DataKey, Vec<RateChange>>(&key)
+    {
+        e.storage().persistent().extend_ttl(
+            &key,
+            INSTANCE_LIFETIME_THRESHOLD,
+            INSTANCE_BUMP_AMOUNT,
+        );
+        schedule
+    } else {
+        Vec::new(e)
+    }
+}
+
+pub fn write_rate_schedule(e: &Env, schedule: &Vec<RateChange>) {
+    let key = DataKey::RewardRateSchedule;
+    if schedule.is_empty() {
+        e.storage().persistent().remove(&key);
+        return;
+    }
+    e.storage().persistent().set(&key, schedule);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+pub fn schedule_rate_change(e: &Env, change: RateChange) {
+    if change.effective_at <= accrual_now(e) {
+        panic_with_error!(e, ExcellarError::InvalidEffectiveLedger)
+    }
+    if change.tick == 0 {
+        panic_with_error!(e, ExcellarError::InvalidRewardTick)
+    }
+    let mut schedule = read_rate_schedule(e);
+    let mut position = schedule.len();
+    for (i, scheduled) in schedule.iter().enumerate() {
+        if scheduled.effective_at >= change.effective_at {
+            position = i as u32;
+            break;
+        }
+    }
+    match schedule.get(position) {
+        // a second change taking effect at the same time replaces the first
+        Some(scheduled) if scheduled.effective_at == change.effective_at => {
+            schedule.set(position, change)
+        }
+        _ if schedule.len() >= MAX_SCHEDULED_RATE_CHANGES => {
+            panic_with_error!(e, ExcellarError::RateScheduleFull)
+        }
+        _ => schedule.insert(position, change),
+    }
+    write_rate_schedule(e, &schedule);
+}
+
+pub fn cancel_rate_change(e: &Env, effective_at: u64) {
+    let mut schedule = read_rate_schedule(e);
+    let Some(position) = schedule
+        .iter()
+        .position(|scheduled| scheduled.effective_at == effective_at)
+    else {
+        panic_with_error!(e, ExcellarError::RateChangeNotScheduled)
+    };
+    schedule.remove(position as u32);
+    write_rate_schedule(e, &schedule);
+}
+
+fn read_history_len(e: &Env) -> u32 {
+    let key = DataKey::RewardRateHistoryLen;
+    if let Some(len) = e.storage().persistent().get::<DataKey, u32>(&key) {
+        e.storage().persistent().extend_ttl(
+            &key,
+            INSTANCE_LIFETIME_THRESHOLD,
+            INSTANCE_BUMP_AMOUNT,
+        );
+        len
+    } else {
+        0
+    }
+}
+
+fn read_history_entry(e: &Env, i: u32) -> RateChange {
+    let key = DataKey::RewardRateHistory(i);
+    let change = e.storage().persistent().get(&key).unwrap();
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    change
+}
+
+fn write_history_entry(e: &Env, i: u32, change: &RateChange) {
+    let key = DataKey::RewardRateHistory(i);
+    e.storage().persistent().set(&key, change);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+/// Appends an applied rate change to the history, a change taking effect at
+/// the same time as the latest entry replaces it.
+pub fn record_rate_change(e: &Env, change: RateChange) {
+    let len = read_history_len(e);
+    if len > 0 && read_history_entry(e, len - 1).effective_at == change.effective_at {
+        write_history_entry(e, len - 1, &change);
+        return;
+    }
+    write_history_entry(e, len, &change);
+
+    let key = DataKey::RewardRateHistoryLen;
+    e.storage().persistent().set(&key, &(len + 1));
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+/// Returns the rate in force at `from` followed by every change effective up
+/// to `to`, including scheduled changes that are due but not yet applied.
+pub fn read_rate_history(e: &Env, from: u64, to: u64) -> Vec<RateChange> {
+    let mut history = Vec::new(e);
+    let len = read_history_len(e);
+
+    // first entry effective after `from`
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = (low + high) / 2;
+        if read_history_entry(e, mid).effective_at <= from {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    for i in low.saturating_sub(1)..len {
+        let change = read_history_entry(e, i);
+        if change.effective_at > to {
+            break;
+        }
+        history.push_back(change);
+    }
+
+    let now = accrual_now(e);
+    for change in read_rate_schedule(e).iter() {
+        if change.effective_at > now || change.effective_at > to {
+            break;
+        }
+        if change.effective_at <= from {
+            history = Vec::from_array(e, [change]);
+        } else {
+            history.push_back(change);
+        }
+    }
+    history
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use soroban_sdk::{testutils::Address as _, vec, Address, Env};
+
+    use crate::error::ExcellarError;
+    use crate::schedule::MAX_SCHEDULED_RATE_CHANGES;
+    use crate::storage_types::{KycTier, RateChange};
+    use crate::test::set_sequence_number;
+
+    fn change(effective_at: u64, rate: u32) -> RateChange {
+        RateChange {
+            effective_at,
+            rate,
+            tick: 28_800,
+        }
+    }
+
+    #[test]
+    fn test_scheduled_rates_integrate_across_segments() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let user = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        let blocks_per_reward: u32 = 28_800;
+        let cycle = u64::from(blocks_per_reward);
+        set_sequence_number(&e, 0);
+        token.set_reward_tick(&admin, &blocks_per_reward);
+        // 1% per cycle
+        token.set_reward_rate(&admin, &1_000_000);
+        token.pass_kyc(&admin, &user, &KycTier::Retail);
+        token.mint(&admin, &user, &1000);
+
+        token.schedule_reward_rate(&admin, &0, &blocks_per_reward, &(cycle * 2));
+        token.schedule_reward_rate(&admin, &2_000_000, &blocks_per_reward, &cycle);
+        assert_eq!(
+            token.scheduled_reward_rates(),
+            vec![&e, change(cycle, 2_000_000), change(cycle * 2, 0)]
+        );
+
+        // due changes show up in the history before anything touches the index
+        set_sequence_number(&e, blocks_per_reward * 3);
+        assert_eq!(token.scheduled_reward_rates(), vec![&e]);
+        assert_eq!(
+            token.reward_rate_history(&0, &(cycle * 3)),
+            vec![
+                &e,
+                change(0, 1_000_000),
+                change(cycle, 2_000_000),
+                change(cycle * 2, 0)
+            ]
+        );
+
+        token.claim_reward(&user);
+        assert_eq!(token.balance(&user), 1030);
+        assert_eq!(
+            token.reward_rate_history(&(cycle + 1), &(cycle * 2)),
+            vec![&e, change(cycle, 2_000_000), change(cycle * 2, 0)]
+        );
+        assert_eq!(
+            token.reward_rate_history(&(cycle * 3), &(cycle * 3)),
+            vec![&e, change(cycle * 2, 0)]
+        );
+    }
+
+    #[test]
+    fn test_cancel_scheduled_rate() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        set_sequence_number(&e, 100);
+
+        assert_eq!(
+            token.try_schedule_reward_rate(&admin, &1, &28_800, &100),
+            Err(Ok(ExcellarError::InvalidEffectiveLedger.into()))
+        );
+        assert_eq!(
+            token.try_cancel_reward_rate(&admin, &200),
+            Err(Ok(ExcellarError::RateChangeNotScheduled.into()))
+        );
+
+        token.schedule_reward_rate(&admin, &1, &28_800, &200);
+        token.schedule_reward_rate(&admin, &2, &28_800, &200);
+        token.schedule_reward_rate(&admin, &3, &28_800, &300);
+        assert_eq!(
+            token.scheduled_reward_rates(),
+            vec![&e, change(200, 2), change(300, 3)]
+        );
+
+        token.cancel_reward_rate(&admin, &300);
+        assert_eq!(token.scheduled_reward_rates(), vec![&e, change(200, 2)]);
+
+        assert_eq!(
+            token.try_schedule_reward_rate(&admin, &1, &0, &300),
+            Err(Ok(ExcellarError::InvalidRewardTick.into()))
+        );
+        for ledger in 1..MAX_SCHEDULED_RATE_CHANGES {
+            token.schedule_reward_rate(&admin, &1, &28_800, &(1000 + u64::from(ledger)));
+        }
+        assert_eq!(
+            token.try_schedule_reward_rate(&admin, &1, &28_800, &300),
+            Err(Ok(ExcellarError::RateScheduleFull.into()))
+        );
+        // replacing a scheduled change does not need room
+        token.schedule_reward_rate(&admin, &2, &28_800, &1001);
+        token.cancel_reward_rate(&admin, &1001);
+        token.schedule_reward_rate(&admin, &3, &28_800, &300);
+        for ledger in 2..MAX_SCHEDULED_RATE_CHANGES {
+            token.cancel_reward_rate(&admin, &(1000 + u64::from(ledger)));
+        }
+        token.cancel_reward_rate(&admin, &300);
+
+        // once effective a change is part of the history
+        set_sequence_number(&e, 200);
+        assert_eq!(
+            token.try_cancel_reward_rate(&admin, &200),
+            Err(Ok(ExcellarError::RateChangeNotScheduled.into()))
+        );
+        assert_eq!(
+            token.reward_rate_history(&200, &200),
+            vec![&e, change(200, 2)]
+        );
+    }
+}
@@ -7,6 +7,10 @@ pub(crate) const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_I
 pub(crate) const BALANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
 pub(crate) const BALANCE_LIFETIME_THRESHOLD: u32 = BALANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
 
+pub(crate) const SECONDS_PER_YEAR: u32 = 365 * 24 * 60 * 60;
+
+pub(crate) const DEFAULT_KYC_VALIDITY: u32 = 365 * DAY_IN_LEDGERS;
+
 #[derive(Clone)]
 #[contracttype]
 pub struct AllowanceDataKey {
@@ -20,6 +24,131 @@ pub struct AllowanceValue {
     pub expiration_ledger: u32,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum KycTier {
+    Retail,
+    Professional,
+    Institutional,
+    // verified before tiers existed, until `pass_kyc` assigns a tier
+    Unassigned,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct KycRecord {
+    pub tier: KycTier,
+    pub verified_at: u32,
+    pub expires_at: u32,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct TierPolicy {
+    pub max_balance: i128,
+    pub max_transfer: i128,
+    pub daily_outflow_cap: i128,
+    pub amm_allowed: bool,
+    pub claim_allowed: bool,
+}
+
+#[contracttype]
+pub struct OutflowWindow {
+    pub day: u32,
+    pub amount: i128,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct IdentityRegistryConfig {
+    pub registry: Address,
+    pub local_fallback: bool,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct PendingAdmin {
+    pub new_admin: Address,
+    pub expiration_ledger: u32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Role {
+    Admin,
+    Minter,
+    Compliance,
+    RewardManager,
+    Guardian,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum PauseScope {
+    Transfer,
+    Mint,
+    Burn,
+    Claim,
+    Approve,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum BlacklistRewardPolicy {
+    Freeze,
+    Forfeit,
+}
+
+/// What happens to an address's reward when its KYC is revoked. `Keep`
+/// leaves it accruing through the revocation, `Freeze` settles it and stops
+/// accrual until KYC is restored, `ForfeitToTreasury` also hands what was
+/// settled to the forgone reward treasury.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum KycRevocationRewardPolicy {
+    Keep,
+    Freeze,
+    ForfeitToTreasury,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum RewardFunding {
+    Mint,
+    // paid out of the treasury's balance through an allowance it grants
+    // this contract
+    Treasury(Address),
+    // paid out of the balance held by this contract
+    Reserve,
+}
+
+/// Asset rewards are paid in instead of this token, transferred out of
+/// `vault` through an allowance the vault grants this contract, or directly
+/// when the vault is this contract.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RewardAsset {
+    pub asset: Address,
+    pub vault: Address,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ShortfallPolicy {
+    Fail,
+    // pays what is available and leaves the rest claimable
+    Partial,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct RoleDataKey {
+    pub role: Role,
+    pub addr: Address,
+}
+
+// Per-account reward record written before the global reward index, settled
+// and replaced by a `RewardSnapshot` the first time the account is touched.
 #[contracttype]
 pub struct AccumulatedReward {
     pub created_ledger_number: u32,
@@ -27,18 +156,117 @@ pub struct AccumulatedReward {
     pub amount: i128,
 }
 
+/// Clock rewards accrue on, chosen at initialization. With `Timestamp` the
+/// reward tick is in seconds and every point in time rewards record,
+/// including when scheduled rate changes take effect, is a unix timestamp.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum AccrualClock {
+    Ledger,
+    Timestamp,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct RewardIndex {
+    pub value: i128,
+    // advances with `value` only while frozen balances earn rewards
+    pub frozen_value: i128,
+    pub last_accrued_at: u64,
+    // when the index was introduced and the parameters in force then, used to
+    // settle `AccumulatedReward` records up to that point
+    pub started_at: u64,
+    pub start_rate: u32,
+    pub start_tick: u32,
+}
+
+// Reward index when balances switched to shares, a share is worth
+// e^(index - reward_index) units from there on.
+#[derive(Clone)]
+#[contracttype]
+pub struct RebaseState {
+    pub started_at: u64,
+    pub reward_index: i128,
+    pub frozen_reward_index: i128,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RateChange {
+    pub effective_at: u64,
+    pub rate: u32,
+    pub tick: u32,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RewardInfo {
+    pub created_at: u64,
+    pub last_accrued_at: u64,
+    pub amount: i128,
+    pub pending: i128,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct RewardSnapshot {
+    pub created_at: u64,
+    pub last_accrued_at: u64,
+    pub index: i128,
+    pub frozen_index: i128,
+    pub amount: i128,
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub enum DataKey {
     Allowance(AllowanceDataKey),
     RewardCheckpoint(Address),
+    RewardSnapshot(Address),
     Kyc(Address),
+    TierPolicy(KycTier),
+    DailyOutflow(Address),
     Blacklisted(Address),
     Amm(Address),
     AmmDepositor(Address),
+    AmmList,
     Balance(Address),
+    Shares(Address),
+    StaticBalance(Address),
+    FrozenBalance(Address),
+    Role(RoleDataKey),
+    RoleAdmin(Role),
+    Paused(PauseScope),
     Admin,
+    PendingAdmin,
+    BlacklistRewardPolicy,
+    KycRevocationRewardPolicy,
+    KycRevoked(Address),
+    KycExpiryReported(Address),
+    KycValidity,
+    IdentityRegistry,
     TotalSupply,
+    TotalShares,
+    TotalStaticBalance,
+    Rebase,
     RewardRate,
     RewardTick,
+    RewardIndex,
+    RewardRateSchedule,
+    RewardRateHistory(u32),
+    RewardRateHistoryLen,
+    FrozenRewardAccrual,
+    AutoCompound,
+    RewardFunding,
+    ShortfallPolicy,
+    RewardAsset,
+    AutoCompoundAccount(Address),
+    RewardClaimer(Address),
+    HolderCount,
+    Holder(u32),
+    HolderIndexed(Address),
+    AccrualClock,
+    RewardExcluded(Address),
+    RewardOptOut(Address),
+    ForgoneRewardTreasury,
 }
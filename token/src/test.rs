@@ -4,18 +4,55 @@ extern crate std;
 use soroban_sdk::testutils::{Ledger, LedgerInfo};
 use soroban_sdk::{
     symbol_short,
-    testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation},
-    Address, Env, IntoVal, Symbol,
+    testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation, Events},
+    token, vec, Address, Env, IntoVal, Symbol,
 };
 
-use crate::{contract::ExcellarToken, ExcellarTokenClient};
+use crate::{
+    contract::ExcellarToken,
+    holder::MAX_BATCH_SIZE,
+    storage_types::{DataKey, DAY_IN_LEDGERS},
+    AccrualClock, BlacklistRewardPolicy, ExcellarError, ExcellarTokenClient, KycRecord,
+    KycRevocationRewardPolicy, KycTier, PauseScope, RewardAsset, RewardFunding, RewardInfo, Role,
+    ShortfallPolicy, TierPolicy,
+};
 
 pub fn create_token<'a>(e: &Env, admin: &Address) -> ExcellarTokenClient<'a> {
     let token = ExcellarTokenClient::new(e, &e.register_contract(None, ExcellarToken {}));
-    token.initialize(admin, &7, &"name".into_val(e), &"symbol".into_val(e));
+    token.initialize(
+        admin,
+        &7,
+        &"name".into_val(e),
+        &"symbol".into_val(e),
+        &AccrualClock::Ledger,
+    );
     token
 }
 
+// ledgers per reward cycle of `start_rewards`
+pub const REWARD_CYCLE: u32 = 28_800;
+
+/// Pays 10% per `REWARD_CYCLE` from ledger 0 and gives every holder a retail
+/// KYC and its balance.
+pub fn start_rewards(
+    e: &Env,
+    token: &ExcellarTokenClient,
+    admin: &Address,
+    holders: &[(&Address, i128)],
+) {
+    set_sequence_number(e, 0);
+    token.set_reward_tick(admin, &REWARD_CYCLE);
+    token.set_reward_rate(admin, &10_000_000);
+    for (holder, balance) in holders {
+        token.pass_kyc(admin, holder, &KycTier::Retail);
+        token.mint(admin, holder, balance);
+    }
+}
+
+fn create_asset<'a>(e: &Env, admin: &Address) -> token::StellarAssetClient<'a> {
+    token::StellarAssetClient::new(e, &e.register_stellar_asset_contract(admin.clone()))
+}
+
 #[test]
 fn test() {
     let e = Env::default();
@@ -27,11 +64,11 @@ fn test() {
     let user2 = Address::generate(&e);
     let user3 = Address::generate(&e);
     let token = create_token(&e, &admin1);
-    token.pass_kyc(&user1);
-    token.pass_kyc(&user2);
-    token.pass_kyc(&user3);
+    token.pass_kyc(&admin1, &user1, &KycTier::Retail);
+    token.pass_kyc(&admin1, &user2, &KycTier::Retail);
+    token.pass_kyc(&admin1, &user3, &KycTier::Retail);
 
-    token.mint(&user1, &1000);
+    token.mint(&admin1, &user1, &1000);
     assert_eq!(
         e.auths(),
         std::vec![(
@@ -40,7 +77,7 @@ fn test() {
                 function: AuthorizedFunction::Contract((
                     token.address.clone(),
                     symbol_short!("mint"),
-                    (&user1, 1000_i128).into_val(&e),
+                    (&admin1, &user1, 1000_i128).into_val(&e),
                 )),
                 sub_invocations: std::vec![],
             }
@@ -105,7 +142,7 @@ fn test() {
     assert_eq!(token.balance(&user1), 500);
     assert_eq!(token.balance(&user3), 300);
 
-    token.set_admin(&admin2);
+    token.propose_admin(&admin2, &200);
     assert_eq!(
         e.auths(),
         std::vec![(
@@ -113,13 +150,31 @@ fn test() {
             AuthorizedInvocation {
                 function: AuthorizedFunction::Contract((
                     token.address.clone(),
-                    symbol_short!("set_admin"),
-                    (&admin2,).into_val(&e),
+                    Symbol::new(&e, "propose_admin"),
+                    (&admin2, 200_u32).into_val(&e),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+
+    token.accept_admin();
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin2.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    Symbol::new(&e, "accept_admin"),
+                    ().into_val(&e),
                 )),
                 sub_invocations: std::vec![],
             }
         )]
     );
+    assert!(token.has_role(&Role::Admin, &admin2));
+    assert!(!token.has_role(&Role::Admin, &admin1));
 
     // Increase to 500
     token.approve(&user2, &user3, &500, &200);
@@ -151,10 +206,10 @@ fn test_mint() {
     let user1 = Address::generate(&e);
     let user2 = Address::generate(&e);
     let token = create_token(&e, &admin);
-    token.pass_kyc(&user1);
-    token.pass_kyc(&user2);
+    token.pass_kyc(&admin, &user1, &KycTier::Retail);
+    token.pass_kyc(&admin, &user2, &KycTier::Retail);
 
-    token.mint(&user1, &1000);
+    token.mint(&admin, &user1, &1000);
     assert_eq!(
         e.auths(),
         std::vec![(
@@ -163,7 +218,7 @@ fn test_mint() {
                 function: AuthorizedFunction::Contract((
                     token.address.clone(),
                     symbol_short!("mint"),
-                    (&user1, 1000_i128).into_val(&e),
+                    (&admin, &user1, 1000_i128).into_val(&e),
                 )),
                 sub_invocations: std::vec![],
             }
@@ -180,8 +235,8 @@ fn test_claim_reward() {
     let admin = Address::generate(&e);
     let user1 = Address::generate(&e);
     let token = create_token(&e, &admin);
-    token.pass_kyc(&user1);
-    token.mint(&user1, &1000);
+    token.pass_kyc(&admin, &user1, &KycTier::Retail);
+    token.mint(&admin, &user1, &1000);
     token.claim_reward(&user1);
 
     assert_eq!(
@@ -210,10 +265,10 @@ fn test_burn() {
     let user1 = Address::generate(&e);
     let user2 = Address::generate(&e);
     let token = create_token(&e, &admin);
-    token.pass_kyc(&user1);
-    token.pass_kyc(&user2);
+    token.pass_kyc(&admin, &user1, &KycTier::Retail);
+    token.pass_kyc(&admin, &user2, &KycTier::Retail);
 
-    token.mint(&user1, &1000);
+    token.mint(&admin, &user1, &1000);
     assert_eq!(token.balance(&user1), 1000);
 
     token.burn(&user1, &500);
@@ -237,7 +292,74 @@ fn test_burn() {
 }
 
 #[test]
-#[should_panic(expected = "insufficient balance")]
+fn test_burn_from() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let spender = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+
+    token.mint(&admin, &user, &1000);
+    token.approve(&user, &spender, &500, &200);
+
+    token.burn_from(&spender, &user, &300);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            spender.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    Symbol::new(&e, "burn_from"),
+                    (&spender, &user, 300_i128).into_val(&e),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+
+    assert_eq!(token.balance(&user), 700);
+    assert_eq!(token.allowance(&user, &spender), 200);
+    assert_eq!(token.total_supply(), 700);
+
+    assert_eq!(
+        token.try_burn_from(&spender, &user, &201),
+        Err(Ok(ExcellarError::InsufficientAllowance.into()))
+    );
+}
+
+#[test]
+fn test_burn_from_compliance_checks() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let spender = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    token.mint(&admin, &user, &1000);
+    token.approve(&user, &spender, &500, &200);
+
+    token.blacklist(&admin, &spender);
+    assert_eq!(
+        token.try_burn_from(&spender, &user, &100),
+        Err(Ok(ExcellarError::Blacklisted.into()))
+    );
+    token.whitelist(&admin, &spender);
+
+    token.fail_kyc(&admin, &user);
+    assert_eq!(
+        token.try_burn_from(&spender, &user, &100),
+        Err(Ok(ExcellarError::KycNotPassed.into()))
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #8)")]
 fn transfer_insufficient_balance() {
     let e = Env::default();
     e.mock_all_auths();
@@ -246,17 +368,17 @@ fn transfer_insufficient_balance() {
     let user1 = Address::generate(&e);
     let user2 = Address::generate(&e);
     let token = create_token(&e, &admin);
-    token.pass_kyc(&user1);
-    token.pass_kyc(&user2);
+    token.pass_kyc(&admin, &user1, &KycTier::Retail);
+    token.pass_kyc(&admin, &user2, &KycTier::Retail);
 
-    token.mint(&user1, &1000);
+    token.mint(&admin, &user1, &1000);
     assert_eq!(token.balance(&user1), 1000);
 
     token.transfer(&user1, &user2, &1001);
 }
 
 #[test]
-#[should_panic(expected = "insufficient allowance")]
+#[should_panic(expected = "Error(Contract, #9)")]
 fn transfer_from_insufficient_allowance() {
     let e = Env::default();
     e.mock_all_auths();
@@ -266,11 +388,11 @@ fn transfer_from_insufficient_allowance() {
     let user2 = Address::generate(&e);
     let user3 = Address::generate(&e);
     let token = create_token(&e, &admin);
-    token.pass_kyc(&user1);
-    token.pass_kyc(&user3);
-    token.pass_kyc(&user2);
+    token.pass_kyc(&admin, &user1, &KycTier::Retail);
+    token.pass_kyc(&admin, &user3, &KycTier::Retail);
+    token.pass_kyc(&admin, &user2, &KycTier::Retail);
 
-    token.mint(&user1, &1000);
+    token.mint(&admin, &user1, &1000);
     assert_eq!(token.balance(&user1), 1000);
 
     token.approve(&user1, &user3, &100, &200);
@@ -280,17 +402,23 @@ fn transfer_from_insufficient_allowance() {
 }
 
 #[test]
-#[should_panic(expected = "already initialized")]
+#[should_panic(expected = "Error(Contract, #1)")]
 fn initialize_already_initialized() {
     let e = Env::default();
     let admin = Address::generate(&e);
     let token = create_token(&e, &admin);
 
-    token.initialize(&admin, &10, &"name".into_val(&e), &"symbol".into_val(&e));
+    token.initialize(
+        &admin,
+        &10,
+        &"name".into_val(&e),
+        &"symbol".into_val(&e),
+        &AccrualClock::Ledger,
+    );
 }
 
 #[test]
-#[should_panic(expected = "Decimal must fit in a u8")]
+#[should_panic(expected = "Error(Contract, #3)")]
 fn decimal_is_over_max() {
     let e = Env::default();
     let admin = Address::generate(&e);
@@ -300,6 +428,7 @@ fn decimal_is_over_max() {
         &(u32::from(u8::MAX) + 1),
         &"name".into_val(&e),
         &"symbol".into_val(&e),
+        &AccrualClock::Ledger,
     );
 }
 
@@ -313,14 +442,14 @@ fn test_zero_allowance() {
     let spender = Address::generate(&e);
     let from = Address::generate(&e);
     let token = create_token(&e, &admin);
-    token.pass_kyc(&spender);
+    token.pass_kyc(&admin, &spender, &KycTier::Retail);
 
     token.transfer_from(&spender, &from, &spender, &0);
     assert!(token.get_allowance(&from, &spender).is_none());
 }
 
 #[test]
-#[should_panic(expected = "address is not passed kyc")]
+#[should_panic(expected = "Error(Contract, #5)")]
 fn test_not_pass_kyc() {
     let e = Env::default();
     e.mock_all_auths();
@@ -333,7 +462,7 @@ fn test_not_pass_kyc() {
 }
 
 #[test]
-#[should_panic(expected = "address is blacklisted")]
+#[should_panic(expected = "Error(Contract, #6)")]
 fn test_blacklisted() {
     let e = Env::default();
     e.mock_all_auths();
@@ -343,175 +472,1692 @@ fn test_blacklisted() {
     let to = Address::generate(&e);
     let token = create_token(&e, &admin);
 
-    token.pass_kyc(&to);
-    token.blacklist(&to);
+    token.pass_kyc(&admin, &to, &KycTier::Retail);
+    token.blacklist(&admin, &to);
 
     token.transfer(&from, &to, &0);
 }
 
 #[test]
-fn test_zero_transfer() {
-    // Here we test that transfer with a 0 amount does not create an empty balance
+fn test_error_codes() {
     let e = Env::default();
     e.mock_all_auths();
 
     let admin = Address::generate(&e);
-    let from = Address::generate(&e);
-    let to = Address::generate(&e);
+    let user = Address::generate(&e);
+    let spender = Address::generate(&e);
+    let amm = Address::generate(&e);
     let token = create_token(&e, &admin);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    token.pass_kyc(&admin, &amm, &KycTier::Retail);
+    token.add_amm_address(&admin, &amm);
+    set_sequence_number(&e, 100);
 
-    token.pass_kyc(&to);
-    token.transfer(&from, &to, &0);
+    assert_eq!(
+        token.try_mint(&admin, &user, &-1),
+        Err(Ok(ExcellarError::NegativeAmount.into()))
+    );
+    assert_eq!(
+        token.try_approve(&user, &spender, &1, &99),
+        Err(Ok(ExcellarError::InvalidExpirationLedger.into()))
+    );
+    assert_eq!(
+        token.try_claim_reward(&amm),
+        Err(Ok(ExcellarError::AmmNotAllowed.into()))
+    );
+}
 
-    assert_eq!(token.balance(&to), 0);
+#[test]
+fn test_admin_proposal() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let typo = Address::generate(&e);
+    let new_admin = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    set_sequence_number(&e, 100);
+
+    assert_eq!(
+        token.try_accept_admin(),
+        Err(Ok(ExcellarError::NoPendingAdmin.into()))
+    );
+    assert_eq!(
+        token.try_propose_admin(&typo, &99),
+        Err(Ok(ExcellarError::InvalidExpirationLedger.into()))
+    );
+
+    // a mistaken proposal can be withdrawn before it is accepted
+    token.propose_admin(&typo, &200);
+    assert_eq!(token.pending_admin().unwrap().new_admin, typo);
+    token.cancel_admin_proposal();
+    assert!(token.pending_admin().is_none());
+    assert!(token.has_role(&Role::Admin, &admin));
+
+    token.propose_admin(&new_admin, &200);
+    set_sequence_number(&e, 201);
+    assert_eq!(
+        token.try_accept_admin(),
+        Err(Ok(ExcellarError::AdminProposalExpired.into()))
+    );
+    assert!(token.has_role(&Role::Admin, &admin));
+
+    token.propose_admin(&new_admin, &300);
+    token.accept_admin();
+    assert!(token.pending_admin().is_none());
+    assert!(token.has_role(&Role::Admin, &new_admin));
+    assert!(!token.has_role(&Role::Admin, &admin));
 }
 
 #[test]
-fn test_pass_kyc() {
+fn test_clawback() {
     let e = Env::default();
     e.mock_all_auths();
 
     let admin = Address::generate(&e);
+    let officer = Address::generate(&e);
     let user = Address::generate(&e);
     let token = create_token(&e, &admin);
+    token.grant_role(&admin, &Role::Compliance, &officer);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    token.mint(&admin, &user, &1000);
 
-    token.pass_kyc(&user);
+    token.clawback(&officer, &user, &400, &7);
     assert_eq!(
         e.auths(),
         std::vec![(
-            admin.clone(),
+            officer.clone(),
             AuthorizedInvocation {
                 function: AuthorizedFunction::Contract((
                     token.address.clone(),
-                    symbol_short!("pass_kyc"),
-                    (&user,).into_val(&e),
+                    Symbol::new(&e, "clawback"),
+                    (&officer, &user, 400_i128, 7_u32).into_val(&e),
                 )),
                 sub_invocations: std::vec![],
             }
         )]
     );
-}
+    assert_eq!(token.balance(&user), 600);
+    assert_eq!(token.total_supply(), 600);
 
-pub fn set_sequence_number(e: &Env, sequence_number: u32) {
-    e.ledger().set(LedgerInfo {
-        timestamp: 12345,
-        protocol_version: 1,
-        sequence_number,
-        network_id: Default::default(),
-        base_reserve: 10,
-        min_temp_entry_ttl: 16,
-        min_persistent_entry_ttl: 16,
-        max_entry_ttl: 100_000,
-    });
+    assert_eq!(
+        token.try_clawback(&admin, &user, &601, &7),
+        Err(Ok(ExcellarError::InsufficientBalance.into()))
+    );
+    token.revoke_role(&admin, &Role::Compliance, &officer);
+    assert_eq!(
+        token.try_clawback(&officer, &user, &100, &7),
+        Err(Ok(ExcellarError::Unauthorized.into()))
+    );
 }
+
 #[test]
-fn test_transfer_with_reward() {
+fn test_force_transfer() {
     let e = Env::default();
     e.mock_all_auths();
 
     let admin = Address::generate(&e);
-    let user1 = Address::generate(&e);
-    let user2 = Address::generate(&e);
+    let officer = Address::generate(&e);
+    let user = Address::generate(&e);
+    let custodian = Address::generate(&e);
     let token = create_token(&e, &admin);
     let blocks_per_reward: u32 = 28_800;
-    let reward_rate: u32 = 1_000_000;
+    token.set_reward_tick(&admin, &blocks_per_reward);
+    token.set_reward_rate(&admin, &30_000_000);
+    token.grant_role(&admin, &Role::Compliance, &officer);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    set_sequence_number(&e, 0);
+    token.mint(&admin, &user, &1000);
+    set_sequence_number(&e, blocks_per_reward);
+    // a blacklisted holder can still have funds moved out
+    token.blacklist(&admin, &user);
 
-    token.set_reward_tick(&blocks_per_reward);
-    token.set_reward_rate(&reward_rate);
+    assert_eq!(
+        token.try_force_transfer(&officer, &user, &custodian, &1000, &3),
+        Err(Ok(ExcellarError::KycNotPassed.into()))
+    );
+    token.pass_kyc(&admin, &custodian, &KycTier::Retail);
 
-    token.pass_kyc(&user1);
-    token.pass_kyc(&user2);
+    token.force_transfer(&officer, &user, &custodian, &1000, &3);
+    assert_eq!(token.balance(&user), 0);
+    assert_eq!(token.balance(&custodian), 1000);
+    assert_eq!(token.total_supply(), 1000);
 
-    set_sequence_number(&e, 0);
-    token.mint(&user1, &1000);
-    token.mint(&user2, &1000);
+    // rewards accrued before the transfer stay with the original holder
+    assert_eq!(token.get_reward(&user), 300);
+    assert_eq!(token.get_reward(&custodian), 0);
+}
+
+#[test]
+fn test_blacklisted_cannot_send_or_approve() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let other = Address::generate(&e);
+    let spender = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    token.pass_kyc(&admin, &other, &KycTier::Retail);
+    token.mint(&admin, &user, &1000);
+    token.mint(&admin, &other, &1000);
+    token.approve(&user, &spender, &500, &200);
+    token.approve(&other, &spender, &500, &200);
+
+    token.blacklist(&admin, &user);
+    assert_eq!(
+        token.try_transfer(&user, &other, &100),
+        Err(Ok(ExcellarError::Blacklisted.into()))
+    );
+    assert_eq!(
+        token.try_approve(&user, &spender, &100, &200),
+        Err(Ok(ExcellarError::Blacklisted.into()))
+    );
+    assert_eq!(
+        token.try_approve(&other, &user, &100, &200),
+        Err(Ok(ExcellarError::Blacklisted.into()))
+    );
+    assert_eq!(
+        token.try_transfer_from(&spender, &user, &other, &100),
+        Err(Ok(ExcellarError::Blacklisted.into()))
+    );
+    assert_eq!(
+        token.try_burn(&user, &100),
+        Err(Ok(ExcellarError::Blacklisted.into()))
+    );
+    assert_eq!(
+        token.try_burn_from(&spender, &user, &100),
+        Err(Ok(ExcellarError::Blacklisted.into()))
+    );
+    assert_eq!(
+        token.try_mint(&admin, &user, &100),
+        Err(Ok(ExcellarError::Blacklisted.into()))
+    );
+
+    token.blacklist(&admin, &spender);
+    assert_eq!(
+        token.try_transfer_from(&spender, &other, &admin, &100),
+        Err(Ok(ExcellarError::Blacklisted.into()))
+    );
+
+    token.whitelist(&admin, &user);
+    token.transfer(&user, &other, &100);
+    assert_eq!(token.balance(&user), 900);
+}
+
+#[test]
+fn test_blacklist_freezes_reward() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let blocks_per_reward: u32 = 28_800;
+    token.set_reward_tick(&admin, &blocks_per_reward);
+    token.set_reward_rate(&admin, &30_000_000);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    assert_eq!(
+        token.blacklist_reward_policy(),
+        BlacklistRewardPolicy::Freeze
+    );
 
+    set_sequence_number(&e, 0);
+    token.mint(&admin, &user, &1000);
     set_sequence_number(&e, blocks_per_reward);
-    token.transfer(&user1, &user2, &300);
-    assert_eq!(token.balance(&user1), 700);
-    assert_eq!(token.balance(&user2), 1300);
+    token.blacklist(&admin, &user);
+    assert_eq!(token.get_reward(&user), 300);
 
     set_sequence_number(&e, blocks_per_reward * 2);
-    token.transfer(&user1, &user2, &300);
-    assert_eq!(token.balance(&user1), 400);
-    assert_eq!(token.balance(&user2), 1600);
+    assert_eq!(
+        token.try_claim_reward(&user),
+        Err(Ok(ExcellarError::Blacklisted.into()))
+    );
+    assert_eq!(
+        token.try_admin_claim_reward(&admin, &user),
+        Err(Ok(ExcellarError::Blacklisted.into()))
+    );
 
+    // accrual resumes from the whitelist ledger, the blacklisted period is not paid
+    token.whitelist(&admin, &user);
     set_sequence_number(&e, blocks_per_reward * 3);
-    token.transfer(&user2, &user1, &600);
-    token.claim_reward(&user1);
-    token.claim_reward(&user2);
-    assert_eq!(token.balance(&user1), 1021);
-    assert_eq!(token.balance(&user2), 1039);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1600);
 }
 
 #[test]
-fn test_transfers_burn_with_reward() {
+fn test_blacklist_forfeits_reward() {
     let e = Env::default();
     e.mock_all_auths();
 
     let admin = Address::generate(&e);
-    let user1 = Address::generate(&e);
-    let user2 = Address::generate(&e);
+    let user = Address::generate(&e);
     let token = create_token(&e, &admin);
     let blocks_per_reward: u32 = 28_800;
-    let reward_rate: u32 = 1_000_000;
-
-    token.set_reward_tick(&blocks_per_reward);
-    token.set_reward_rate(&reward_rate);
-    token.pass_kyc(&user1);
-    token.pass_kyc(&user2);
+    token.set_reward_tick(&admin, &blocks_per_reward);
+    token.set_reward_rate(&admin, &30_000_000);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    token.set_blacklist_reward_policy(&admin, &BlacklistRewardPolicy::Forfeit);
 
     set_sequence_number(&e, 0);
-    token.mint(&user1, &1000);
-    token.mint(&user2, &1000);
+    token.mint(&admin, &user, &1000);
+    set_sequence_number(&e, blocks_per_reward);
+    token.blacklist(&admin, &user);
+    assert_eq!(token.get_reward(&user), 0);
+
+    set_sequence_number(&e, blocks_per_reward * 2);
+    token.whitelist(&admin, &user);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1000);
+}
+
+#[test]
+fn test_kyc_revocation_freezes_reward() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let blocks_per_reward: u32 = 28_800;
+    token.set_reward_tick(&admin, &blocks_per_reward);
+    token.set_reward_rate(&admin, &30_000_000);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    assert_eq!(
+        token.kyc_revocation_reward_policy(),
+        KycRevocationRewardPolicy::Freeze
+    );
 
+    set_sequence_number(&e, 0);
+    token.mint(&admin, &user, &1000);
     set_sequence_number(&e, blocks_per_reward);
-    token.transfer(&user1, &user2, &300);
-    assert_eq!(token.balance(&user1), 700);
-    assert_eq!(token.balance(&user2), 1300);
+    token.fail_kyc(&admin, &user);
+    assert_eq!(token.get_reward(&user), 300);
 
     set_sequence_number(&e, blocks_per_reward * 2);
-    token.burn(&user1, &300);
-    token.claim_reward(&user1);
-    token.claim_reward(&user2);
-    assert_eq!(token.balance(&user1), 417);
-    assert_eq!(token.balance(&user2), 1323);
+    assert_eq!(
+        token.try_claim_reward(&user),
+        Err(Ok(ExcellarError::KycNotPassed.into()))
+    );
+    // nor can a delegated claimer sweep it elsewhere
+    let custodian = Address::generate(&e);
+    token.pass_kyc(&admin, &custodian, &KycTier::Institutional);
+    token.set_reward_claimer(&user, &Some(custodian.clone()));
+    assert_eq!(
+        token.try_claim_reward_to(&user, &custodian),
+        Err(Ok(ExcellarError::KycNotPassed.into()))
+    );
+
+    // accrual resumes once KYC is restored, the revoked period is not paid
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    set_sequence_number(&e, blocks_per_reward * 3);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1600);
 }
 
 #[test]
-fn test_mint_and_reward_cf() {
+fn test_kyc_revocation_forfeits_reward_to_treasury() {
     let e = Env::default();
     e.mock_all_auths();
+
     let admin = Address::generate(&e);
-    let user1 = Address::generate(&e);
+    let user = Address::generate(&e);
+    let treasury = Address::generate(&e);
     let token = create_token(&e, &admin);
     let blocks_per_reward: u32 = 28_800;
-    let reward_rate: u32 = 3_00_00;
-    token.set_reward_tick(&blocks_per_reward);
-    token.set_reward_rate(&reward_rate);
-    token.pass_kyc(&user1);
+    token.set_reward_tick(&admin, &blocks_per_reward);
+    token.set_reward_rate(&admin, &30_000_000);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    token.pass_kyc(&admin, &treasury, &KycTier::Institutional);
+    // forfeiting needs somewhere to send the reward
+    assert_eq!(
+        token.try_set_kyc_revocation_reward_policy(
+            &admin,
+            &KycRevocationRewardPolicy::ForfeitToTreasury
+        ),
+        Err(Ok(ExcellarError::ForgoneRewardTreasuryNotSet.into()))
+    );
+    token.set_forgone_reward_treasury(&admin, &Some(treasury.clone()));
+    token.set_kyc_revocation_reward_policy(&admin, &KycRevocationRewardPolicy::ForfeitToTreasury);
+    assert_eq!(
+        token.try_set_forgone_reward_treasury(&admin, &None),
+        Err(Ok(ExcellarError::ForgoneRewardTreasuryNotSet.into()))
+    );
+
     set_sequence_number(&e, 0);
-    token.mint(&user1, &10_000);
+    token.mint(&admin, &user, &1000);
+    set_sequence_number(&e, blocks_per_reward);
+    token.fail_kyc(&admin, &user);
+    assert_eq!(token.get_reward(&user), 0);
+    assert_eq!(token.get_reward(&treasury), 300);
+
     set_sequence_number(&e, blocks_per_reward * 2);
-    token.claim_reward(&user1);
-    assert_eq!(token.balance(&user1), 10_006);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1000);
 }
 
 #[test]
-fn test_mint_and_reward_cf_admin() {
+fn test_kyc_revocation_keeps_reward() {
     let e = Env::default();
     e.mock_all_auths();
+
     let admin = Address::generate(&e);
-    let user1 = Address::generate(&e);
+    let user = Address::generate(&e);
     let token = create_token(&e, &admin);
     let blocks_per_reward: u32 = 28_800;
-    let reward_rate: u32 = 30_000_000;
-    token.set_reward_tick(&blocks_per_reward);
-    token.set_reward_rate(&reward_rate);
-    token.pass_kyc(&user1);
+    token.set_reward_tick(&admin, &blocks_per_reward);
+    token.set_reward_rate(&admin, &30_000_000);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    token.set_kyc_revocation_reward_policy(&admin, &KycRevocationRewardPolicy::Keep);
+
     set_sequence_number(&e, 0);
-    token.mint(&user1, &1000);
+    token.mint(&admin, &user, &1000);
+    set_sequence_number(&e, blocks_per_reward);
+    token.fail_kyc(&admin, &user);
+
+    // the revoked period accrues and is paid once KYC is restored
     set_sequence_number(&e, blocks_per_reward * 2);
-    token.admin_claim_reward(&user1);
-    assert_eq!(token.balance(&user1), 1600);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1600);
+}
+
+#[test]
+fn test_freeze_amount() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let officer = Address::generate(&e);
+    let user = Address::generate(&e);
+    let other = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    token.grant_role(&admin, &Role::Compliance, &officer);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    token.pass_kyc(&admin, &other, &KycTier::Retail);
+    token.mint(&admin, &user, &1000);
+
+    token.freeze_amount(&officer, &user, &600);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            officer.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    Symbol::new(&e, "freeze_amount"),
+                    (&officer, &user, 600_i128).into_val(&e),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+    assert_eq!(token.frozen_balance(&user), 600);
+    assert_eq!(token.available_balance(&user), 400);
+    assert_eq!(token.balance(&user), 1000);
+
+    token.transfer(&user, &other, &400);
+    assert_eq!(
+        token.try_transfer(&user, &other, &1),
+        Err(Ok(ExcellarError::BalanceFrozen.into()))
+    );
+    assert_eq!(
+        token.try_burn(&user, &1),
+        Err(Ok(ExcellarError::BalanceFrozen.into()))
+    );
+    assert_eq!(
+        token.try_freeze_amount(&officer, &user, &1),
+        Err(Ok(ExcellarError::InsufficientBalance.into()))
+    );
+    assert_eq!(
+        token.try_unfreeze_amount(&officer, &user, &601),
+        Err(Ok(ExcellarError::InsufficientFrozenBalance.into()))
+    );
+
+    token.unfreeze_amount(&officer, &user, &200);
+    token.transfer(&user, &other, &200);
+    assert_eq!(token.balance(&user), 400);
+    assert_eq!(token.available_balance(&user), 0);
+
+    // enforcement actions reach frozen funds
+    token.clawback(&officer, &user, &300, &1);
+    assert_eq!(token.balance(&user), 100);
+    assert_eq!(token.frozen_balance(&user), 100);
+}
+
+#[test]
+fn test_frozen_reward_accrual() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let blocks_per_reward: u32 = 28_800;
+    token.set_reward_tick(&admin, &blocks_per_reward);
+    token.set_reward_rate(&admin, &30_000_000);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    assert!(token.frozen_reward_accrual());
+
+    set_sequence_number(&e, 0);
+    token.mint(&admin, &user, &1000);
+    token.freeze_amount(&admin, &user, &500);
+
+    set_sequence_number(&e, blocks_per_reward);
+    token.transfer(&user, &user, &0);
+    assert_eq!(token.get_reward(&user), 300);
+
+    // only the 500 available tokens earn from here on
+    token.set_frozen_reward_accrual(&admin, &false);
+    set_sequence_number(&e, blocks_per_reward * 2);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1450);
+}
+
+#[test]
+fn test_frozen_reward_accrual_change_is_not_retroactive() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let blocks_per_reward: u32 = 28_800;
+    token.set_reward_tick(&admin, &blocks_per_reward);
+    token.set_reward_rate(&admin, &30_000_000);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+
+    set_sequence_number(&e, 0);
+    token.mint(&admin, &user, &1000);
+    token.freeze_amount(&admin, &user, &500);
+
+    // the user is not checkpointed when the policy changes, the frozen 500
+    // still earned for the first cycle
+    set_sequence_number(&e, blocks_per_reward);
+    token.set_frozen_reward_accrual(&admin, &false);
+    set_sequence_number(&e, blocks_per_reward * 2);
+    assert_eq!(token.pending_reward(&user), 450);
+
+    token.set_frozen_reward_accrual(&admin, &true);
+    set_sequence_number(&e, blocks_per_reward * 3);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1750);
+}
+
+#[test]
+fn test_lapsed_kyc_does_not_earn_rewards() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    set_sequence_number(&e, 0);
+    token.set_reward_tick(&admin, &DAY_IN_LEDGERS);
+    token.set_reward_rate(&admin, &10_000_000);
+    token.set_kyc_validity(&admin, &DAY_IN_LEDGERS);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    token.mint(&admin, &user, &1000);
+
+    set_sequence_number(&e, DAY_IN_LEDGERS);
+    token.transfer(&user, &user, &0);
+    assert_eq!(token.get_reward(&user), 100);
+
+    // renewing two days after the expiry does not pay for those days
+    set_sequence_number(&e, DAY_IN_LEDGERS * 3);
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    assert_eq!(token.get_reward(&user), 100);
+    set_sequence_number(&e, DAY_IN_LEDGERS * 4);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1200);
+}
+
+#[test]
+fn test_kyc_expiry() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    set_sequence_number(&e, 100);
+    token.set_kyc_validity(&admin, &1000);
+    assert_eq!(token.kyc_validity(), 1000);
+    assert_eq!(token.kyc_status(&user), None);
+
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    assert_eq!(
+        token.kyc_status(&user),
+        Some(KycRecord {
+            tier: KycTier::Retail,
+            verified_at: 100,
+            expires_at: 1100,
+        })
+    );
+    token.mint(&admin, &user, &1000);
+
+    set_sequence_number(&e, 1100);
+    token.burn(&user, &100);
+
+    set_sequence_number(&e, 1101);
+    assert_eq!(
+        token.try_burn(&user, &100),
+        Err(Ok(ExcellarError::KycExpired.into()))
+    );
+    assert_eq!(
+        token.try_claim_reward(&user),
+        Err(Ok(ExcellarError::KycExpired.into()))
+    );
+
+    // the expiry is reported once, by the first call that writes state
+    let expired_events = |e: &Env| {
+        let topics = (Symbol::new(e, "kyc_expired"), user.clone()).into_val(e);
+        e.events()
+            .all()
+            .iter()
+            .filter(|(_, event_topics, _)| *event_topics == topics)
+            .count()
+    };
+    token.pending_reward(&user);
+    assert_eq!(expired_events(&e), 0);
+    token.transfer(&admin, &user, &0);
+    assert_eq!(expired_events(&e), 1);
+    token.transfer(&admin, &user, &0);
+    assert_eq!(expired_events(&e), 1);
+
+    // re-verification starts a new window
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    assert_eq!(
+        token.kyc_status(&user),
+        Some(KycRecord {
+            tier: KycTier::Retail,
+            verified_at: 1101,
+            expires_at: 2101,
+        })
+    );
+    token.burn(&user, &100);
+    assert_eq!(token.balance(&user), 800);
+}
+
+#[test]
+fn test_legacy_kyc_record() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    token.set_kyc_validity(&admin, &1000);
+    e.as_contract(&token.address, || {
+        e.storage()
+            .persistent()
+            .set(&DataKey::Kyc(user.clone()), &true);
+    });
+
+    // the validity window starts when the record is migrated
+    set_sequence_number(&e, 100);
+    token.mint(&admin, &user, &1000);
+    assert_eq!(token.balance(&user), 1000);
+    assert_eq!(
+        token.kyc_status(&user),
+        Some(KycRecord {
+            tier: KycTier::Unassigned,
+            verified_at: 100,
+            expires_at: 1100,
+        })
+    );
+
+    set_sequence_number(&e, 1101);
+    assert_eq!(
+        token.try_mint(&admin, &user, &1000),
+        Err(Ok(ExcellarError::KycExpired.into()))
+    );
+}
+
+#[test]
+fn test_zero_transfer() {
+    // Here we test that transfer with a 0 amount does not create an empty balance
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let from = Address::generate(&e);
+    let to = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    token.pass_kyc(&admin, &to, &KycTier::Retail);
+    token.transfer(&from, &to, &0);
+
+    assert_eq!(token.balance(&to), 0);
+}
+
+#[test]
+fn test_pass_kyc() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    token.pass_kyc(&admin, &user, &KycTier::Retail);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    symbol_short!("pass_kyc"),
+                    (&admin, &user, KycTier::Retail).into_val(&e),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+}
+
+pub fn set_timestamp(e: &Env, timestamp: u64) {
+    e.ledger().with_mut(|li| li.timestamp = timestamp);
+}
+
+pub fn set_sequence_number(e: &Env, sequence_number: u32) {
+    e.ledger().set(LedgerInfo {
+        timestamp: 12345,
+        protocol_version: 1,
+        sequence_number,
+        network_id: Default::default(),
+        base_reserve: 10,
+        min_temp_entry_ttl: 16,
+        min_persistent_entry_ttl: 16,
+        max_entry_ttl: 100_000,
+    });
+}
+#[test]
+fn test_transfer_with_reward() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let blocks_per_reward: u32 = 28_800;
+    let reward_rate: u32 = 1_000_000;
+
+    token.set_reward_tick(&admin, &blocks_per_reward);
+    token.set_reward_rate(&admin, &reward_rate);
+
+    token.pass_kyc(&admin, &user1, &KycTier::Retail);
+    token.pass_kyc(&admin, &user2, &KycTier::Retail);
+
+    set_sequence_number(&e, 0);
+    token.mint(&admin, &user1, &1000);
+    token.mint(&admin, &user2, &1000);
+
+    set_sequence_number(&e, blocks_per_reward);
+    token.transfer(&user1, &user2, &300);
+    assert_eq!(token.balance(&user1), 700);
+    assert_eq!(token.balance(&user2), 1300);
+
+    set_sequence_number(&e, blocks_per_reward * 2);
+    token.transfer(&user1, &user2, &300);
+    assert_eq!(token.balance(&user1), 400);
+    assert_eq!(token.balance(&user2), 1600);
+
+    set_sequence_number(&e, blocks_per_reward * 3);
+    token.transfer(&user2, &user1, &600);
+    token.claim_reward(&user1);
+    token.claim_reward(&user2);
+    assert_eq!(token.balance(&user1), 1021);
+    assert_eq!(token.balance(&user2), 1039);
+}
+
+#[test]
+fn test_transfers_burn_with_reward() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let blocks_per_reward: u32 = 28_800;
+    let reward_rate: u32 = 1_000_000;
+
+    token.set_reward_tick(&admin, &blocks_per_reward);
+    token.set_reward_rate(&admin, &reward_rate);
+    token.pass_kyc(&admin, &user1, &KycTier::Retail);
+    token.pass_kyc(&admin, &user2, &KycTier::Retail);
+
+    set_sequence_number(&e, 0);
+    token.mint(&admin, &user1, &1000);
+    token.mint(&admin, &user2, &1000);
+
+    set_sequence_number(&e, blocks_per_reward);
+    token.transfer(&user1, &user2, &300);
+    assert_eq!(token.balance(&user1), 700);
+    assert_eq!(token.balance(&user2), 1300);
+
+    set_sequence_number(&e, blocks_per_reward * 2);
+    token.burn(&user1, &300);
+    token.claim_reward(&user1);
+    token.claim_reward(&user2);
+    assert_eq!(token.balance(&user1), 417);
+    assert_eq!(token.balance(&user2), 1323);
+}
+
+#[test]
+fn test_mint_and_reward_cf() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let blocks_per_reward: u32 = 28_800;
+    let reward_rate: u32 = 3_00_00;
+    token.set_reward_tick(&admin, &blocks_per_reward);
+    token.set_reward_rate(&admin, &reward_rate);
+    token.pass_kyc(&admin, &user1, &KycTier::Retail);
+    set_sequence_number(&e, 0);
+    token.mint(&admin, &user1, &10_000);
+    set_sequence_number(&e, blocks_per_reward * 2);
+    token.claim_reward(&user1);
+    assert_eq!(token.balance(&user1), 10_006);
+}
+
+#[test]
+fn test_mint_and_reward_cf_admin() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let blocks_per_reward: u32 = 28_800;
+    let reward_rate: u32 = 30_000_000;
+    token.set_reward_tick(&admin, &blocks_per_reward);
+    token.set_reward_rate(&admin, &reward_rate);
+    token.pass_kyc(&admin, &user1, &KycTier::Retail);
+    set_sequence_number(&e, 0);
+    token.mint(&admin, &user1, &1000);
+    set_sequence_number(&e, blocks_per_reward * 2);
+    token.admin_claim_reward(&admin, &user1);
+    assert_eq!(token.balance(&user1), 1600);
+}
+
+#[test]
+fn test_pool_share_does_not_reset_depositor_accrual() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let amm = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&user1, 1000)]);
+    token.pass_kyc(&admin, &user2, &KycTier::Retail);
+    token.add_amm_address(&admin, &amm);
+    token.transfer(&user1, &amm, &500);
+
+    // someone else touching the pool credits user1 its share of the pool
+    // without settling what user1 earns on the 500 it kept
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.transfer(&user2, &amm, &0);
+    assert_eq!(token.get_reward(&user1), 50);
+
+    // 100 on the 500 kept plus 100 on the 500 in the pool; when crediting
+    // the share restarted user1's own accrual only the pool's 100 was paid
+    set_sequence_number(&e, REWARD_CYCLE * 2);
+    token.transfer(&user2, &amm, &0);
+    token.claim_reward(&user1);
+    assert_eq!(token.balance(&user1), 700);
+}
+
+#[test]
+fn test_rebasing_mints_do_not_inflate_supply() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&user1, 1000)]);
+    token.pass_kyc(&admin, &user2, &KycTier::Retail);
+    token.enable_rebasing(&admin);
+
+    // a share is worth e^0.1 units, so small amounts never convert exactly
+    set_sequence_number(&e, REWARD_CYCLE);
+    let supply = token.total_supply();
+    let mut minted = 0;
+    for amount in 1..=40 {
+        let to = if amount % 2 == 0 { &user1 } else { &user2 };
+        token.mint(&admin, to, &amount);
+        minted += amount;
+        assert!(token.total_supply() <= supply + minted);
+    }
+    assert!(token.total_supply() >= supply + minted - 40);
+}
+
+#[test]
+fn test_rebasing_balances_grow() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let user3 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&user1, 1000), (&user2, 500)]);
+    token.pass_kyc(&admin, &user3, &KycTier::Retail);
+    assert_eq!(token.shares_of(&user1), 1000);
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    assert!(!token.rebasing());
+    token.enable_rebasing(&admin);
+    assert!(token.rebasing());
+    assert_eq!(
+        token.try_enable_rebasing(&admin),
+        Err(Ok(ExcellarError::AlreadyRebasing.into()))
+    );
+    assert_eq!(token.shares_of(&user1), 1000);
+    assert_eq!(token.total_shares(), 1500);
+    assert_eq!(token.balance(&user1), 1000);
+
+    // a share grows by e^0.1 over a 10% cycle
+    set_sequence_number(&e, REWARD_CYCLE * 2);
+    assert_eq!(token.balance(&user1), 1105);
+    assert_eq!(token.balance(&user2), 552);
+    assert_eq!(token.total_supply(), 1657);
+
+    // rewards accrued before the switch stay claimable, nothing accrues after
+    assert_eq!(token.pending_reward(&user2), 50);
+    token.claim_reward(&user1);
+    assert_eq!(token.balance(&user1), 1204);
+    assert_eq!(token.shares_of(&user1), 1090);
+    assert_eq!(token.pending_reward(&user1), 0);
+
+    // the sender's shares round up and the recipient's down
+    token.transfer(&user1, &user3, &550);
+    assert_eq!(token.balance(&user1), 654);
+    assert_eq!(token.balance(&user3), 549);
+    assert_eq!(token.shares_of(&user3), 497);
+    assert_eq!(token.total_shares(), 1589);
+    assert_eq!(token.total_supply(), 1756);
+
+    token.burn(&user3, &549);
+    assert_eq!(token.balance(&user3), 0);
+    assert_eq!(token.total_shares(), 1092);
+}
+
+#[test]
+fn test_frozen_reward_before_rebasing() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&user, 1000)]);
+    token.set_frozen_reward_accrual(&admin, &false);
+    token.freeze_amount(&admin, &user, &500);
+
+    // settled once shares are worth more than a unit, the reward is
+    // still on the 500 tokens that were not frozen before the switch
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.enable_rebasing(&admin);
+    set_sequence_number(&e, REWARD_CYCLE * 3);
+    assert_eq!(token.pending_reward(&user), 50);
+    assert_eq!(token.balance(&user), 1221);
+    token.claim_reward(&user);
+    assert_eq!(token.get_reward(&user), 0);
+    // paid in shares, rounded down
+    assert_eq!(token.balance(&user), 1269);
+}
+
+#[test]
+fn test_rebasing_growth_does_not_depend_on_activity() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let busy = Address::generate(&e);
+    let idle = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(
+        &e,
+        &token,
+        &admin,
+        &[(&busy, 1_000_000), (&idle, 1_000_000)],
+    );
+    token.set_reward_tick(&admin, &2_880);
+    token.enable_rebasing(&admin);
+
+    for sequence in 1..=20 {
+        set_sequence_number(&e, sequence * 1_440);
+        e.budget().reset_default();
+        token.transfer(&busy, &busy, &0);
+    }
+    // ten cycles at 10% compound to e^1
+    assert_eq!(token.balance(&idle), 2_718_281);
+    assert!((token.balance(&busy) - token.balance(&idle)).abs() <= 1);
+}
+
+#[test]
+fn test_rebasing_follows_eligibility() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let earner = Address::generate(&e);
+    let blocked = Address::generate(&e);
+    let excluded = Address::generate(&e);
+    let pool = Address::generate(&e);
+    let frozen = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(
+        &e,
+        &token,
+        &admin,
+        &[
+            (&earner, 1000),
+            (&blocked, 1000),
+            (&excluded, 1000),
+            (&pool, 1000),
+            (&frozen, 1000),
+        ],
+    );
+    token.set_frozen_reward_accrual(&admin, &false);
+    token.enable_rebasing(&admin);
+
+    token.blacklist(&admin, &blocked);
+    token.exclude_from_rewards(&admin, &excluded);
+    token.add_amm_address(&admin, &pool);
+    token.freeze_amount(&admin, &frozen, &500);
+
+    // only what earns rewards grows, the rest keeps a static balance
+    set_sequence_number(&e, REWARD_CYCLE);
+    assert_eq!(token.balance(&earner), 1105);
+    assert_eq!(token.balance(&blocked), 1000);
+    assert_eq!(token.balance(&excluded), 1000);
+    assert_eq!(token.balance(&pool), 1000);
+    assert_eq!(token.balance(&frozen), 1052);
+    assert_eq!(token.total_supply(), 5157);
+
+    // growth resumes from the ledger the address is eligible again
+    token.whitelist(&admin, &blocked);
+    token.unfreeze_amount(&admin, &frozen, &500);
+    set_sequence_number(&e, REWARD_CYCLE * 2);
+    assert_eq!(token.balance(&blocked), 1104);
+    assert_eq!(token.balance(&frozen), 1162);
+
+    // the lapse of KYC is picked up by the next checkpoint
+    token.set_kyc_validity(&admin, &0);
+    token.pass_kyc(&admin, &earner, &KycTier::Retail);
+    set_sequence_number(&e, REWARD_CYCLE * 2 + 1);
+    token.distribute_rewards(&admin, &0, &10);
+    let balance = token.balance(&earner);
+    set_sequence_number(&e, REWARD_CYCLE * 3);
+    assert_eq!(token.balance(&earner), balance);
+}
+
+#[test]
+fn test_rebasing_requires_minted_rewards() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    token.set_reward_funding(&admin, &RewardFunding::Reserve);
+    assert_eq!(
+        token.try_enable_rebasing(&admin),
+        Err(Ok(ExcellarError::RebasingRequiresMinting.into()))
+    );
+
+    token.set_reward_funding(&admin, &RewardFunding::Mint);
+    let other = Address::generate(&e);
+    assert_eq!(
+        token.try_enable_rebasing(&other),
+        Err(Ok(ExcellarError::Unauthorized.into()))
+    );
+    token.enable_rebasing(&admin);
+    assert_eq!(
+        token.try_set_reward_funding(&admin, &RewardFunding::Reserve),
+        Err(Ok(ExcellarError::RebasingRequiresMinting.into()))
+    );
+}
+
+#[test]
+fn test_reserve_funded_rewards() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&user, 1000), (&admin, 100)]);
+
+    assert_eq!(token.reward_funding(), RewardFunding::Mint);
+    assert_eq!(token.available_reward_funds(), i128::MAX);
+    let other = Address::generate(&e);
+    assert_eq!(
+        token.try_set_reward_funding(&other, &RewardFunding::Reserve),
+        Err(Ok(ExcellarError::Unauthorized.into()))
+    );
+    token.set_reward_funding(&admin, &RewardFunding::Reserve);
+    token.fund_reward_reserve(&admin, &100);
+    assert_eq!(token.reward_reserve(), 100);
+    assert_eq!(token.available_reward_funds(), 100);
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1100);
+    assert_eq!(token.reward_reserve(), 0);
+    assert_eq!(token.total_supply(), 1100);
+    assert_eq!(token.balance(&admin), 0);
+
+    set_sequence_number(&e, REWARD_CYCLE * 2);
+    assert_eq!(
+        token.try_claim_reward(&user),
+        Err(Ok(ExcellarError::InsufficientRewardFunds.into()))
+    );
+    assert_eq!(token.pending_reward(&user), 110);
+
+    token.mint(&admin, &admin, &100);
+    token.fund_reward_reserve(&admin, &100);
+    assert_eq!(
+        token.try_withdraw_reward_reserve(&other, &other, &40),
+        Err(Ok(ExcellarError::Unauthorized.into()))
+    );
+    token.withdraw_reward_reserve(&admin, &admin, &40);
+    assert_eq!(token.reward_reserve(), 60);
+    assert_eq!(token.balance(&admin), 40);
+}
+
+#[test]
+fn test_partial_payout_on_shortfall() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&user, 1000), (&admin, 40)]);
+
+    token.set_reward_funding(&admin, &RewardFunding::Reserve);
+    token.set_reward_shortfall_policy(&admin, &ShortfallPolicy::Partial);
+    assert_eq!(token.reward_shortfall_policy(), ShortfallPolicy::Partial);
+    token.fund_reward_reserve(&admin, &40);
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1040);
+    assert_eq!(token.reward_reserve(), 0);
+    // the unpaid part stays claimable
+    assert_eq!(token.get_reward(&user), 60);
+
+    token.mint(&admin, &admin, &60);
+    token.fund_reward_reserve(&admin, &60);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1100);
+    assert_eq!(token.get_reward(&user), 0);
+}
+
+#[test]
+fn test_funding_the_reserve_is_checked_like_a_transfer() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let funder = Address::generate(&e);
+    let amm = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&funder, 1000)]);
+    token.set_reward_funding(&admin, &RewardFunding::Reserve);
+
+    token.fail_kyc(&admin, &funder);
+    assert_eq!(
+        token.try_fund_reward_reserve(&funder, &100),
+        Err(Ok(ExcellarError::KycNotPassed.into()))
+    );
+    token.pass_kyc(&admin, &funder, &KycTier::Retail);
+    token.set_tier_policy(
+        &admin,
+        &KycTier::Retail,
+        &TierPolicy {
+            max_balance: i128::MAX,
+            max_transfer: 300,
+            daily_outflow_cap: i128::MAX,
+            amm_allowed: true,
+            claim_allowed: true,
+        },
+    );
+    assert_eq!(
+        token.try_fund_reward_reserve(&funder, &400),
+        Err(Ok(ExcellarError::TierTransferLimit.into()))
+    );
+    token.fund_reward_reserve(&funder, &300);
+    assert_eq!(token.reward_reserve(), 300);
+
+    // funding out of a pool takes it out of its depositors' shares
+    token.pass_kyc(&admin, &user1, &KycTier::Institutional);
+    token.pass_kyc(&admin, &user2, &KycTier::Institutional);
+    token.pass_kyc(&admin, &amm, &KycTier::Institutional);
+    token.add_amm_address(&admin, &amm);
+    token.mint(&admin, &user1, &500);
+    token.mint(&admin, &user2, &500);
+    token.transfer(&user1, &amm, &500);
+    token.transfer(&user2, &amm, &500);
+    token.fund_reward_reserve(&amm, &500);
+    assert_eq!(token.reward_reserve(), 800);
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.transfer(&amm, &user1, &0);
+    assert_eq!(token.pending_reward(&user1), 25);
+    assert_eq!(token.pending_reward(&user2), 25);
+}
+
+#[test]
+fn test_treasury_funded_rewards() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let treasury = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&user, 1000), (&treasury, 1000)]);
+
+    token.set_reward_funding(&admin, &RewardFunding::Treasury(treasury.clone()));
+    // nothing can be paid before the treasury grants an allowance
+    assert_eq!(token.available_reward_funds(), 0);
+    token.approve(&treasury, &token.address, &150, &(REWARD_CYCLE * 2));
+    assert_eq!(token.available_reward_funds(), 150);
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1100);
+    assert_eq!(token.balance(&treasury), 900);
+    assert_eq!(token.allowance(&treasury, &token.address), 50);
+    assert_eq!(token.total_supply(), 2000);
+
+    // the treasury keeps earning on its own balance
+    assert_eq!(token.get_reward(&treasury), 100);
+
+    set_sequence_number(&e, REWARD_CYCLE * 2);
+    assert_eq!(
+        token.try_claim_reward(&user),
+        Err(Ok(ExcellarError::InsufficientRewardFunds.into()))
+    );
+}
+
+#[test]
+fn test_auto_compound_pays_what_is_available() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&user, 1000), (&admin, 30)]);
+    token.set_reward_funding(&admin, &RewardFunding::Reserve);
+    token.fund_reward_reserve(&admin, &30);
+    token.set_auto_compound(&user, &true);
+
+    // a shortfall is only compounded partially when claims pay partially
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.transfer(&user, &user, &0);
+    assert_eq!(token.balance(&user), 1000);
+    assert_eq!(token.get_reward(&user), 100);
+
+    token.set_reward_shortfall_policy(&admin, &ShortfallPolicy::Partial);
+    token.transfer(&user, &user, &0);
+    assert_eq!(token.balance(&user), 1030);
+    assert_eq!(token.get_reward(&user), 70);
+}
+
+#[test]
+fn test_rewards_paid_in_asset_from_vault() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let vault = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let usdc = create_asset(&e, &admin);
+    let usdc_token = token::Client::new(&e, &usdc.address);
+    start_rewards(&e, &token, &admin, &[(&user, 1000)]);
+    token.set_auto_compound(&user, &true);
+
+    usdc.mint(&vault, &150);
+    usdc_token.approve(&vault, &token.address, &1000, &(REWARD_CYCLE * 3));
+    let reward_asset = RewardAsset {
+        asset: usdc.address.clone(),
+        vault: vault.clone(),
+    };
+    token.set_reward_asset(&admin, &Some(reward_asset.clone()));
+    assert_eq!(token.reward_asset(), Some(reward_asset));
+    assert_eq!(token.available_reward_funds(), 150);
+
+    // auto-compounding does not apply to rewards paid in another asset
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.transfer(&user, &user, &0);
+    assert_eq!(token.balance(&user), 1000);
+
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1000);
+    assert_eq!(token.total_supply(), 1000);
+    assert_eq!(usdc_token.balance(&user), 100);
+    assert_eq!(usdc_token.balance(&vault), 50);
+    assert_eq!(usdc_token.allowance(&vault, &token.address), 900);
+
+    set_sequence_number(&e, REWARD_CYCLE * 2);
+    assert_eq!(
+        token.try_claim_reward(&user),
+        Err(Ok(ExcellarError::InsufficientRewardFunds.into()))
+    );
+
+    token.set_reward_asset(&admin, &None);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1100);
+}
+
+#[test]
+fn test_asset_decimals_are_converted() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = ExcellarTokenClient::new(&e, &e.register_contract(None, ExcellarToken {}));
+    token.initialize(
+        &admin,
+        &6,
+        &"name".into_val(&e),
+        &"symbol".into_val(&e),
+        &AccrualClock::Ledger,
+    );
+    let usdc = create_asset(&e, &admin);
+    let usdc_token = token::Client::new(&e, &usdc.address);
+    start_rewards(&e, &token, &admin, &[(&user, 1000)]);
+
+    // the contract itself holds the asset
+    usdc.mint(&token.address, &5000);
+    token.set_reward_asset(
+        &admin,
+        &Some(RewardAsset {
+            asset: usdc.address.clone(),
+            vault: token.address.clone(),
+        }),
+    );
+    assert_eq!(token.available_reward_funds(), 500);
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.claim_reward(&user);
+    assert_eq!(usdc_token.balance(&user), 1000);
+    assert_eq!(usdc_token.balance(&token.address), 4000);
+}
+
+#[test]
+fn test_asset_rounding_stays_claimable() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = ExcellarTokenClient::new(&e, &e.register_contract(None, ExcellarToken {}));
+    token.initialize(
+        &admin,
+        &9,
+        &"name".into_val(&e),
+        &"symbol".into_val(&e),
+        &AccrualClock::Ledger,
+    );
+    let usdc = create_asset(&e, &admin);
+    let usdc_token = token::Client::new(&e, &usdc.address);
+    start_rewards(&e, &token, &admin, &[(&user, 1050)]);
+
+    usdc.mint(&token.address, &5000);
+    let other = Address::generate(&e);
+    let reward_asset = Some(RewardAsset {
+        asset: usdc.address.clone(),
+        vault: token.address.clone(),
+    });
+    assert_eq!(
+        token.try_set_reward_asset(&other, &reward_asset),
+        Err(Ok(ExcellarError::Unauthorized.into()))
+    );
+    token.set_reward_asset(&admin, &reward_asset);
+
+    // 105 units are one unit of the asset and change, the change is kept
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.claim_reward(&user);
+    assert_eq!(usdc_token.balance(&user), 1);
+    assert_eq!(token.get_reward(&user), 5);
+
+    // and less than a unit of the asset is not paid at all
+    token.claim_reward(&user);
+    assert_eq!(usdc_token.balance(&user), 1);
+    assert_eq!(token.get_reward(&user), 5);
+}
+
+#[test]
+fn test_admin_claim_rewards_skips_ineligible() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let user3 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&user1, 1000), (&user2, 1000)]);
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.blacklist(&admin, &user2);
+    token.admin_claim_rewards(&admin, &vec![&e, user1.clone(), user2.clone(), user3]);
+    assert_eq!(token.balance(&user1), 1100);
+    assert_eq!(token.balance(&user2), 1000);
+
+    let mut addresses = vec![&e];
+    for _ in 0..=MAX_BATCH_SIZE {
+        addresses.push_back(user1.clone());
+    }
+    assert_eq!(
+        token.try_admin_claim_rewards(&admin, &addresses),
+        Err(Ok(ExcellarError::BatchTooLarge.into()))
+    );
+    token.pause(&admin, &PauseScope::Claim);
+    assert_eq!(
+        token.try_admin_claim_rewards(&admin, &vec![&e, user1]),
+        Err(Ok(ExcellarError::Paused.into()))
+    );
+}
+
+#[test]
+fn test_distribute_rewards_walks_holder_index() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let holders: std::vec::Vec<Address> = (0..5).map(|_| Address::generate(&e)).collect();
+    let balances: std::vec::Vec<_> = holders.iter().map(|holder| (holder, 1000)).collect();
+    start_rewards(&e, &token, &admin, &balances);
+    // receiving again does not index twice
+    token.transfer(&holders[0], &holders[1], &0);
+    assert_eq!(token.holder_count(), 5);
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    assert_eq!(token.distribute_rewards(&admin, &0, &2), Some(2));
+    assert_eq!(token.balance(&holders[1]), 1100);
+    assert_eq!(token.balance(&holders[2]), 1000);
+    assert_eq!(token.distribute_rewards(&admin, &2, &2), Some(4));
+    assert_eq!(token.distribute_rewards(&admin, &4, &2), None);
+    for holder in holders.iter() {
+        assert_eq!(token.balance(holder), 1100);
+    }
+    assert_eq!(token.distribute_rewards(&admin, &5, &2), None);
+}
+
+#[test]
+fn test_distribution_drops_empty_holders() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let amm = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let holders: std::vec::Vec<Address> = (0..4).map(|_| Address::generate(&e)).collect();
+    let balances: std::vec::Vec<_> = holders.iter().map(|holder| (holder, 1000)).collect();
+    start_rewards(&e, &token, &admin, &balances);
+    token.pass_kyc(&admin, &amm, &KycTier::Retail);
+    token.add_amm_address(&admin, &amm);
+    token.transfer(&holders[1], &holders[0], &1000);
+    token.transfer(&holders[2], &amm, &1000);
+    assert_eq!(token.holder_count(), 5);
+
+    // a pool depositor holds nothing until the pool is settled, it is
+    // indexed again once the pool credits it
+    set_sequence_number(&e, REWARD_CYCLE);
+    assert_eq!(token.distribute_rewards(&admin, &2, &1), Some(2));
+    assert_eq!(token.holder_count(), 4);
+    token.transfer(&amm, &holders[0], &0);
+    assert_eq!(token.holder_count(), 5);
+
+    // the last holder takes the place of an emptied one and is still
+    // visited in the same pass
+    assert_eq!(token.distribute_rewards(&admin, &0, &10), None);
+    assert_eq!(token.holder_count(), 4);
+    assert_eq!(token.balance(&holders[0]), 2200);
+    assert_eq!(token.balance(&holders[2]), 100);
+    assert_eq!(token.balance(&holders[3]), 1100);
+
+    token.transfer(&holders[0], &holders[1], &100);
+    assert_eq!(token.holder_count(), 5);
+}
+
+#[test]
+fn test_distribution_batch_fits_default_budget() {
+    let e = Env::default();
+    e.mock_all_auths();
+    e.budget().reset_unlimited();
+
+    let admin = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let mut addresses = vec![&e];
+    for _ in 0..MAX_BATCH_SIZE {
+        addresses.push_back(Address::generate(&e));
+    }
+    let holders: std::vec::Vec<Address> = addresses.iter().collect();
+    let balances: std::vec::Vec<_> = holders.iter().map(|holder| (holder, 1000)).collect();
+    start_rewards(&e, &token, &admin, &balances);
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    e.budget().reset_default();
+    assert_eq!(token.distribute_rewards(&admin, &0, &u32::MAX), None);
+    let (cpu, mem) = (
+        e.budget().cpu_instruction_cost(),
+        e.budget().memory_bytes_cost(),
+    );
+    assert!(cpu < 100_000_000, "cpu {cpu}");
+    assert!(mem < 40 * 1024 * 1024, "mem {mem}");
+
+    set_sequence_number(&e, REWARD_CYCLE * 2);
+    e.budget().reset_default();
+    token.admin_claim_rewards(&admin, &addresses);
+    assert!(e.budget().cpu_instruction_cost() < 100_000_000);
+    assert!(e.budget().memory_bytes_cost() < 40 * 1024 * 1024);
+    assert_eq!(token.balance(&addresses.get(0).unwrap()), 1210);
+}
+
+#[test]
+fn test_pending_reward_view() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&user, 1000)]);
+    // 1% per cycle
+    token.set_reward_rate(&admin, &1_000_000);
+
+    set_sequence_number(&e, REWARD_CYCLE * 2);
+    assert_eq!(token.pending_reward(&user), 20);
+    assert_eq!(token.get_reward(&user), 0);
+    assert_eq!(
+        token.reward_info(&user),
+        RewardInfo {
+            created_at: 0,
+            last_accrued_at: 0,
+            amount: 0,
+            pending: 20,
+        }
+    );
+
+    token.transfer(&user, &user, &0);
+    set_sequence_number(&e, REWARD_CYCLE * 3);
+    assert_eq!(
+        token.reward_info(&user),
+        RewardInfo {
+            created_at: 0,
+            last_accrued_at: (REWARD_CYCLE * 2).into(),
+            amount: 20,
+            pending: 10,
+        }
+    );
+    assert_eq!(token.pending_reward(&user), 30);
+    token.claim_reward(&user);
+    assert_eq!(token.balance(&user), 1030);
+    assert_eq!(token.pending_reward(&user), 0);
+}
+
+#[test]
+fn test_pending_reward_includes_amm_share() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let amm = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&user1, 1000), (&user2, 1000)]);
+    token.add_amm_address(&admin, &amm);
+    token.transfer(&user1, &amm, &600);
+    token.transfer(&user2, &amm, &200);
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    // own balance plus three quarters of the pool's 80
+    assert_eq!(token.pending_reward(&user1), 40 + 60);
+    assert_eq!(token.pending_reward(&user2), 80 + 20);
+    assert_eq!(token.get_reward(&user1), 0);
+
+    // pool shares are settled when the pool itself is touched
+    token.transfer(&amm, &amm, &0);
+    token.claim_reward(&user1);
+    token.claim_reward(&user2);
+    assert_eq!(token.balance(&user1), 400 + 100);
+    assert_eq!(token.balance(&user2), 800 + 100);
+}
+
+#[test]
+fn test_auto_compound_account() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&user, 1000)]);
+    assert!(!token.auto_compound(&user));
+    token.set_auto_compound(&user, &true);
+    assert!(token.auto_compound(&user));
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.transfer(&user, &user, &0);
+    assert_eq!(token.balance(&user), 1100);
+    assert_eq!(token.get_reward(&user), 0);
+
+    set_sequence_number(&e, REWARD_CYCLE * 2);
+    token.transfer(&user, &user, &0);
+    assert_eq!(token.balance(&user), 1210);
+    assert_eq!(token.total_supply(), 1210);
+
+    // opting out leaves later rewards to be claimed
+    token.set_auto_compound(&user, &false);
+    set_sequence_number(&e, REWARD_CYCLE * 3);
+    token.transfer(&user, &user, &0);
+    assert_eq!(token.balance(&user), 1210);
+    assert_eq!(token.get_reward(&user), 121);
+}
+
+#[test]
+fn test_default_auto_compound() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    token.set_default_auto_compound(&admin, &true);
+    assert!(token.default_auto_compound());
+    start_rewards(&e, &token, &admin, &[(&user1, 1000), (&user2, 1000)]);
+    token.set_auto_compound(&user2, &false);
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    token.transfer(&user1, &user2, &0);
+    assert_eq!(token.balance(&user1), 1100);
+    assert_eq!(token.balance(&user2), 1000);
+    assert_eq!(token.get_reward(&user2), 100);
+
+    // compounding is a claim and respects the claim pause
+    token.pause(&admin, &PauseScope::Claim);
+    set_sequence_number(&e, REWARD_CYCLE * 2);
+    token.transfer(&user1, &user1, &0);
+    assert_eq!(token.balance(&user1), 1100);
+    assert_eq!(token.get_reward(&user1), 110);
+
+    token.unpause(&admin, &PauseScope::Claim);
+    token.transfer(&user1, &user1, &0);
+    assert_eq!(token.balance(&user1), 1210);
+}
+
+#[test]
+fn test_claim_reward_to_recipient_through_claimer() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let owner = Address::generate(&e);
+    let custody = Address::generate(&e);
+    let recipient = Address::generate(&e);
+    let outsider = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    start_rewards(&e, &token, &admin, &[(&owner, 1000)]);
+    token.pass_kyc(&admin, &recipient, &KycTier::Retail);
+
+    token.set_reward_claimer(&owner, &Some(custody.clone()));
+    assert_eq!(token.reward_claimer(&owner), Some(custody.clone()));
+
+    set_sequence_number(&e, REWARD_CYCLE);
+    assert_eq!(
+        token.try_claim_reward_to(&owner, &outsider),
+        Err(Ok(ExcellarError::KycNotPassed.into()))
+    );
+    token.claim_reward_to(&owner, &recipient);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            custody.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    Symbol::new(&e, "claim_reward_to"),
+                    (&owner, &recipient).into_val(&e),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+    assert_eq!(token.balance(&recipient), 100);
+    assert_eq!(token.balance(&owner), 1000);
+    assert_eq!(token.get_reward(&owner), 0);
+
+    // without a claimer the owner authorizes the claim
+    token.set_reward_claimer(&owner, &None);
+    assert_eq!(token.reward_claimer(&owner), None);
+    set_sequence_number(&e, REWARD_CYCLE * 2);
+    token.claim_reward_to(&owner, &recipient);
+    assert_eq!(e.auths()[0].0, owner);
+    assert_eq!(token.balance(&recipient), 200);
 }
@@ -0,0 +1,310 @@
+use soroban_sdk::{panic_with_error, Address, Env};
+
+use crate::admin::{is_amm, read_kyc};
+use crate::balance::read_balance;
+use crate::error::ExcellarError;
+use crate::storage_types::{
+    DataKey, KycTier, OutflowWindow, TierPolicy, DAY_IN_LEDGERS, INSTANCE_BUMP_AMOUNT,
+    INSTANCE_LIFETIME_THRESHOLD,
+};
+
+pub fn read_tier_policy(e: &Env, tier: KycTier) -> TierPolicy {
+    let key = DataKey::TierPolicy(tier);
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().get(&key).unwrap_or(TierPolicy {
+        max_balance: i128::MAX,
+        max_transfer: i128::MAX,
+        daily_outflow_cap: i128::MAX,
+        amm_allowed: true,
+        claim_allowed: true,
+    })
+}
+
+pub fn write_tier_policy(e: &Env, tier: KycTier, policy: TierPolicy) {
+    let key = DataKey::TierPolicy(tier);
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    e.storage().instance().set(&key, &policy);
+}
+
+fn read_address_policy(e: &Env, addr: Address) -> Option<TierPolicy> {
+    read_kyc(e, addr).map(|record| read_tier_policy(e, record.tier))
+}
+
+fn record_outflow(e: &Env, addr: Address, amount: i128, cap: i128) {
+    if cap == i128::MAX {
+        return;
+    }
+    let key = DataKey::DailyOutflow(addr);
+    let day = e.ledger().sequence() / DAY_IN_LEDGERS;
+    let spent = match e.storage().temporary().get::<DataKey, OutflowWindow>(&key) {
+        Some(window) if window.day == day => window.amount,
+        _ => 0,
+    };
+    if amount > cap - spent {
+        panic_with_error!(e, ExcellarError::TierDailyOutflowLimit)
+    }
+    e.storage().temporary().set(
+        &key,
+        &OutflowWindow {
+            day,
+            amount: spent + amount,
+        },
+    );
+    e.storage()
+        .temporary()
+        .extend_ttl(&key, DAY_IN_LEDGERS, DAY_IN_LEDGERS);
+}
+
+pub fn is_within_balance_limit(e: &Env, to: Address, amount: i128) -> bool {
+    match read_address_policy(e, to.clone()) {
+        Some(policy) => amount <= policy.max_balance - read_balance(e, to),
+        None => true,
+    }
+}
+
+pub fn check_balance_limit(e: &Env, to: Address, amount: i128) {
+    if !is_within_balance_limit(e, to, amount) {
+        panic_with_error!(e, ExcellarError::TierBalanceLimit)
+    }
+}
+
+// burns leave the holder like a transfer does and count towards the same cap
+pub fn check_outflow_limit(e: &Env, from: Address, amount: i128) {
+    if let Some(policy) = read_address_policy(e, from.clone()) {
+        record_outflow(e, from, amount, policy.daily_outflow_cap);
+    }
+}
+
+pub fn is_claim_allowed(e: &Env, addr: Address) -> bool {
+    match read_address_policy(e, addr) {
+        Some(policy) => policy.claim_allowed,
+        None => true,
+    }
+}
+
+pub fn check_claim_allowed(e: &Env, addr: Address) {
+    if !is_claim_allowed(e, addr) {
+        panic_with_error!(e, ExcellarError::TierClaimNotAllowed)
+    }
+}
+
+pub fn check_transfer_limits(e: &Env, from: Address, to: Address, amount: i128) {
+    if let Some(policy) = read_address_policy(e, from.clone()) {
+        if amount > policy.max_transfer {
+            panic_with_error!(e, ExcellarError::TierTransferLimit)
+        }
+        if !policy.amm_allowed && is_amm(e, to.clone()) {
+            panic_with_error!(e, ExcellarError::TierAmmNotAllowed)
+        }
+        record_outflow(e, from.clone(), amount, policy.daily_outflow_cap);
+    }
+    if let Some(policy) = read_address_policy(e, to.clone()) {
+        if !policy.amm_allowed && is_amm(e, from) {
+            panic_with_error!(e, ExcellarError::TierAmmNotAllowed)
+        }
+        if amount > policy.max_balance - read_balance(e, to) {
+            panic_with_error!(e, ExcellarError::TierBalanceLimit)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use soroban_sdk::{testutils::Address as _, vec, Address, Env};
+
+    use crate::error::ExcellarError;
+    use crate::storage_types::{KycTier, TierPolicy, DAY_IN_LEDGERS};
+    use crate::test::set_sequence_number;
+
+    fn retail_policy() -> TierPolicy {
+        TierPolicy {
+            max_balance: 1000,
+            max_transfer: 300,
+            daily_outflow_cap: 500,
+            amm_allowed: false,
+            claim_allowed: false,
+        }
+    }
+
+    #[test]
+    fn test_default_tier_policy_is_unlimited() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+
+        let policy = token.tier_policy(&KycTier::Retail);
+        assert_eq!(policy.max_balance, i128::MAX);
+        assert_eq!(policy.max_transfer, i128::MAX);
+        assert_eq!(policy.daily_outflow_cap, i128::MAX);
+        assert!(policy.amm_allowed);
+        assert!(policy.claim_allowed);
+
+        token.set_tier_policy(&admin, &KycTier::Retail, &retail_policy());
+        assert_eq!(token.tier_policy(&KycTier::Retail), retail_policy());
+        assert_eq!(
+            token.tier_policy(&KycTier::Institutional).max_balance,
+            i128::MAX
+        );
+    }
+
+    #[test]
+    fn test_tier_balance_and_transfer_limits() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let retail = Address::generate(&e);
+        let institution = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        token.set_tier_policy(&admin, &KycTier::Retail, &retail_policy());
+        token.pass_kyc(&admin, &retail, &KycTier::Retail);
+        token.pass_kyc(&admin, &institution, &KycTier::Institutional);
+
+        assert_eq!(
+            token.try_mint(&admin, &retail, &1001),
+            Err(Ok(ExcellarError::TierBalanceLimit.into()))
+        );
+        token.mint(&admin, &retail, &1000);
+        token.mint(&admin, &institution, &10_000);
+
+        assert_eq!(
+            token.try_transfer(&institution, &retail, &1),
+            Err(Ok(ExcellarError::TierBalanceLimit.into()))
+        );
+        assert_eq!(
+            token.try_transfer(&retail, &institution, &301),
+            Err(Ok(ExcellarError::TierTransferLimit.into()))
+        );
+        token.transfer(&retail, &institution, &300);
+        token.transfer(&institution, &retail, &300);
+        assert_eq!(token.balance(&retail), 1000);
+    }
+
+    #[test]
+    fn test_tier_daily_outflow_cap() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let retail = Address::generate(&e);
+        let other = Address::generate(&e);
+        let spender = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        token.set_tier_policy(&admin, &KycTier::Retail, &retail_policy());
+        token.pass_kyc(&admin, &retail, &KycTier::Retail);
+        token.pass_kyc(&admin, &other, &KycTier::Institutional);
+        set_sequence_number(&e, 0);
+        token.mint(&admin, &retail, &1000);
+        token.approve(&retail, &spender, &1000, &(DAY_IN_LEDGERS * 2));
+
+        token.transfer(&retail, &other, &300);
+        assert_eq!(
+            token.try_transfer_from(&spender, &retail, &other, &201),
+            Err(Ok(ExcellarError::TierDailyOutflowLimit.into()))
+        );
+        token.transfer_from(&spender, &retail, &other, &200);
+
+        // the cap resets on the next day
+        set_sequence_number(&e, DAY_IN_LEDGERS);
+        token.transfer(&retail, &other, &300);
+        assert_eq!(token.balance(&retail), 200);
+    }
+
+    #[test]
+    fn test_tier_amm_and_claim_restrictions() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let retail = Address::generate(&e);
+        let professional = Address::generate(&e);
+        let amm = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        token.set_tier_policy(&admin, &KycTier::Retail, &retail_policy());
+        token.pass_kyc(&admin, &retail, &KycTier::Retail);
+        token.pass_kyc(&admin, &professional, &KycTier::Professional);
+        token.add_amm_address(&admin, &amm);
+        token.mint(&admin, &retail, &100);
+        token.mint(&admin, &professional, &100);
+
+        assert_eq!(
+            token.try_transfer(&retail, &amm, &100),
+            Err(Ok(ExcellarError::TierAmmNotAllowed.into()))
+        );
+        token.transfer(&professional, &amm, &100);
+        assert_eq!(
+            token.try_transfer(&amm, &retail, &100),
+            Err(Ok(ExcellarError::TierAmmNotAllowed.into()))
+        );
+
+        assert_eq!(
+            token.try_claim_reward(&retail),
+            Err(Ok(ExcellarError::TierClaimNotAllowed.into()))
+        );
+        assert_eq!(
+            token.try_admin_claim_reward(&admin, &retail),
+            Err(Ok(ExcellarError::TierClaimNotAllowed.into()))
+        );
+        token.claim_reward(&professional);
+    }
+
+    #[test]
+    fn test_tier_limits_apply_to_rewards_and_burns() {
+        let e = Env::default();
+        e.mock_all_auths();
+
+        let admin = Address::generate(&e);
+        let retail = Address::generate(&e);
+        let spender = Address::generate(&e);
+        let token = crate::test::create_token(&e, &admin);
+        token.set_tier_policy(
+            &admin,
+            &KycTier::Retail,
+            &TierPolicy {
+                claim_allowed: true,
+                ..retail_policy()
+            },
+        );
+        token.pass_kyc(&admin, &retail, &KycTier::Retail);
+        set_sequence_number(&e, 0);
+        token.set_reward_tick(&admin, &28_800);
+        token.set_reward_rate(&admin, &10_000_000);
+        token.mint(&admin, &retail, &1000);
+
+        // a reward paid in this token cannot lift the balance over the limit
+        set_sequence_number(&e, 28_800);
+        assert_eq!(
+            token.try_claim_reward(&retail),
+            Err(Ok(ExcellarError::TierBalanceLimit.into()))
+        );
+        token.admin_claim_rewards(&admin, &vec![&e, retail.clone()]);
+        token.set_auto_compound(&retail, &true);
+        assert_eq!(token.balance(&retail), 1000);
+        assert_eq!(token.get_reward(&retail), 100);
+
+        // burns count towards the daily outflow cap
+        token.approve(&retail, &spender, &1000, &(DAY_IN_LEDGERS * 3));
+        token.burn(&retail, &300);
+        assert_eq!(token.balance(&retail), 700);
+        assert_eq!(
+            token.try_burn(&retail, &201),
+            Err(Ok(ExcellarError::TierDailyOutflowLimit.into()))
+        );
+        assert_eq!(
+            token.try_burn_from(&spender, &retail, &201),
+            Err(Ok(ExcellarError::TierDailyOutflowLimit.into()))
+        );
+        // once there is room the reward compounds
+        token.burn_from(&spender, &retail, &200);
+        assert_eq!(token.balance(&retail), 600);
+        assert_eq!(token.get_reward(&retail), 0);
+    }
+}